@@ -0,0 +1,106 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use bevy::prelude::*;
+
+use crate::config::SimConfig;
+use crate::daynight::GameTime;
+use crate::{Bobble, Target};
+
+/// Births buffered before they're written out, and the most simulated
+/// seconds any of them waits, so the log stays small however long the run.
+const FLUSH_SIZE: usize = 256;
+const FLUSH_INTERVAL: f32 = 10.;
+
+/// A bobble's id in the family tree. Unlike its entity, it's never reused.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineageId(pub u64);
+
+/// One birth: who was born, to whom, in which generation and when, in game
+/// time.
+struct Birth {
+    child: LineageId,
+    parents: Vec<LineageId>,
+    generation: u32,
+    time: f32,
+}
+
+/// Hands out lineage ids and logs every birth to the `ancestry_log` file as
+/// CSV, one row per bobble with its parents' ids separated by spaces.
+/// Founders, spawned rather than born, have none. Births are buffered and
+/// then written, or dropped when there's no log.
+#[derive(Resource)]
+pub struct Ancestry {
+    next_id: u64,
+    pending: Vec<Birth>,
+    since_flush: f32,
+    log: Option<BufWriter<File>>,
+}
+
+impl FromWorld for Ancestry {
+    fn from_world(world: &mut World) -> Self {
+        let log = world.resource::<SimConfig>().ancestry_log.as_ref().and_then(|path| {
+            let opened = File::create(path).and_then(|file| {
+                let mut log = BufWriter::new(file);
+                writeln!(log, "child,parents,generation,time")?;
+                Ok(log)
+            });
+            opened.inspect_err(|e| println!("Couldn't create {}: {}", path, e)).ok()
+        });
+        Self {
+            next_id: 0,
+            pending: Vec::new(),
+            since_flush: 0.,
+            log,
+        }
+    }
+}
+
+impl Ancestry {
+    /// A fresh id for a bobble born to `parents`, recording the birth.
+    pub fn record(&mut self, parents: &[LineageId], generation: u32, time: f32) -> LineageId {
+        let child = LineageId(self.next_id);
+        self.next_id += 1;
+        self.pending.push(Birth { child, parents: parents.to_vec(), generation, time });
+        child
+    }
+
+    fn flush(&mut self) {
+        let pending = std::mem::take(&mut self.pending);
+        let Some(log) = self.log.as_mut() else {
+            return;
+        };
+        let written = pending.iter().try_for_each(|birth| {
+            let parents: Vec<String> = birth.parents.iter().map(|p| p.0.to_string()).collect();
+            writeln!(log, "{},{},{},{:.2}", birth.child.0, parents.join(" "), birth.generation, birth.time)
+        }).and_then(|_| log.flush());
+        if let Err(e) = written {
+            println!("Couldn't write the ancestry log: {}", e);
+            self.log = None;
+        }
+    }
+}
+
+/// Gives every bobble that appeared without a lineage id, the seeded,
+/// reseeded and injected ones, an id as a founder with no parents.
+pub fn tag_founders(
+    mut commands: Commands,
+    game_time: Res<GameTime>,
+    mut ancestry: ResMut<Ancestry>,
+    founders: Query<(Entity, &Bobble), (Added<Bobble>, Without<LineageId>, Without<Target>)>,
+) {
+    for (entity, bobble) in &founders {
+        let id = ancestry.record(&[], bobble.generation, game_time.elapsed);
+        commands.entity(entity).insert(id);
+    }
+}
+
+/// Writes out the buffered births once there are `FLUSH_SIZE` of them or
+/// `FLUSH_INTERVAL` has passed.
+pub fn flush_ancestry(time: Res<Time>, mut ancestry: ResMut<Ancestry>) {
+    ancestry.since_flush += time.delta_secs();
+    if ancestry.pending.len() >= FLUSH_SIZE || ancestry.since_flush >= FLUSH_INTERVAL {
+        ancestry.since_flush = 0.;
+        ancestry.flush();
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use neat::{Connectivity, Float, Genome, InnovationHistory, NeuralNetwork, WeightInit};
+use rand::Rng;
+
+use crate::obstacle::Obstacle;
+use crate::pheromone::Pheromones;
+use crate::spatial::{SpatialGrid, SpatialIndex};
+use crate::thirst::{Thirst, THIRST_ENABLED};
+use crate::vision::{Heading, VisionCone, VISION_INPUTS, VISION_SEES_BOBBLES};
+use crate::world::WorldWrap;
+use crate::config::{ControlScheme, MovementModel, OutputCurve, SimConfig};
+use crate::daynight::GameTime;
+use crate::{Bobble, Energy, Fitness, Hunger, LifetimeStats, Movement, Plant, Target};
+
+/// Sensors fed to every brain, in input node order:
+/// bias, plant distance per vision sector, obstacle distance per sector,
+/// bobble distance per sector if bobbles are seen, flock centre offset (x, y)
+/// and flock velocity (x, y), nearest corpse smell direction (x, y) and
+/// distance, pheromone gradient direction (x, y) and level, hunger, energy,
+/// then with thirst on nearest water direction (x, y), nearest water distance
+/// and thirst.
+pub const BRAIN_INPUTS: usize =
+    3 + VISION_INPUTS + FLOCK_INPUTS + SMELL_INPUTS + PHEROMONE_INPUTS + if THIRST_ENABLED { 4 } else { 0 };
+const FLOCK_INPUTS: usize = 4;
+const SMELL_INPUTS: usize = 3;
+const PHEROMONE_INPUTS: usize = 3;
+/// Desired movement direction (x, y), or the push to give it under
+/// `MovementModel::Inertial`.
+pub const BRAIN_OUTPUTS: usize = 2;
+
+/// Passes a recurrent brain makes each think.
+const RECURRENT_STEPS: usize = 3;
+
+/// Innovation numbers and node ids shared by every brain in the world.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct Innovations(pub InnovationHistory);
+
+/// The velocity a bobble carries from step to step under
+/// `MovementModel::Inertial`. Unused when brains set velocity directly.
+#[derive(Component, Default)]
+pub struct Momentum(pub Vec2);
+
+#[derive(Component)]
+pub struct Brain {
+    pub genome: Genome,
+    pub network: NeuralNetwork,
+    /// Nodes plus enabled connections, which sets how much the brain costs to run.
+    pub complexity: usize,
+    /// Network passes per think, which lets recurrent brains settle before
+    /// they act. Feed-forward brains only ever need one.
+    pub steps: usize,
+}
+
+impl Brain {
+    pub fn new(genome: Genome) -> Self {
+        let network = genome.compile();
+        let (nodes, connections) = genome.complexity();
+        let steps = if network.is_recurrent() { RECURRENT_STEPS } else { 1 };
+        Self { genome, network, complexity: nodes + connections, steps }
+    }
+
+    /// A fresh minimal brain with random weights.
+    pub fn random(innovations: &mut InnovationHistory, rng: &mut impl Rng) -> Self {
+        Self::new(Genome::with_hidden(BRAIN_INPUTS, BRAIN_OUTPUTS, Connectivity::Full, 0, WeightInit::default(), innovations, rng))
+    }
+
+    /// Runs the network on `inputs` for `steps` passes and gives back its
+    /// outputs.
+    pub fn decide(&mut self, inputs: &[f32]) -> Vec<f32> {
+        let inputs: Vec<Float> = inputs.iter().map(|&input| input as Float).collect();
+        self.network.activate_steps(&inputs, self.steps).into_iter().map(from_network).collect()
+    }
+}
+
+/// A network value, which is a `Float`, as the `f32` the rest of the world
+/// uses. Everything read out of a network goes through here.
+#[allow(clippy::unnecessary_cast)] // A no-op unless neat is built with f64
+pub fn from_network(value: Float) -> f32 {
+    value as f32
+}
+
+/// Everything in the world a bobble can sense besides other bobbles, the
+/// scent they leave included.
+#[derive(SystemParam)]
+pub struct Surroundings<'w, 's> {
+    plants: Query<'w, 's, &'static Transform, (With<Plant>, Without<Bobble>)>,
+    obstacles: Query<'w, 's, (&'static Transform, &'static Obstacle), Without<Bobble>>,
+    pheromones: Res<'w, Pheromones>,
+    /// Where the other bobbles, corpses and water were when the step began.
+    index: Res<'w, SpatialIndex>,
+}
+
+/// Feed each bobble's senses through its brain and move it the way it decides.
+pub fn think(
+    time: Res<Time>,
+    config: Res<SimConfig>,
+    game_time: Res<GameTime>,
+    wrap: Res<WorldWrap>,
+    mut bobbles: Query<(Entity, &mut Transform, &mut Heading, &Movement, &Bobble, &Hunger, &Energy, Option<&Thirst>, &mut Brain, &mut Momentum), Without<Target>>,
+    surroundings: Surroundings,
+) {
+    let obstacles: Vec<(Vec2, f32)> = surroundings.obstacles.iter()
+        .map(|(transform, obstacle)| (transform.translation.truncate(), obstacle.radius))
+        .collect();
+
+    // Paired with the index's positions from before anyone moves this step,
+    // so every bobble sees the same world
+    let velocities: HashMap<Entity, Vec2> = bobbles.iter()
+        .map(|(entity, _, _, movement, ..)| (entity, movement.velocity))
+        .collect();
+    let index = &surroundings.index;
+
+    // Bobbles see less far in the dark
+    let vision_range = config.vision_range * game_time.scale(&config, config.night_vision);
+
+    bobbles.iter_mut().for_each(|(entity, mut transform, mut heading, _, bobble, hunger, energy, thirst, mut brain, mut momentum)| {
+        let position = transform.translation.truncate();
+        let cone = VisionCone {
+            position,
+            heading: heading.0,
+            range: vision_range,
+            fov: config.vision_fov,
+            wrap: wrap.0,
+        };
+        let plant_positions = surroundings.plants.iter().map(|plant| plant.translation.truncate());
+
+        let mut inputs = vec![1.]; // Bias
+        inputs.extend(cone.sense(plant_positions, &obstacles));
+        inputs.extend(cone.sense_obstacles(&obstacles));
+        if VISION_SEES_BOBBLES {
+            let bobble_positions = index.bobbles.near(position, vision_range, wrap.0)
+                .filter(|(other, _)| *other != entity)
+                .map(|(_, p)| p);
+            inputs.extend(cone.sense(bobble_positions, &obstacles));
+        }
+        let neighbours = index.bobbles.k_nearest(
+            position,
+            config.flock_radius,
+            wrap.0,
+            config.flock_neighbours.unwrap_or(usize::MAX),
+            |other| *other != entity,
+        );
+        let flock = neighbours.iter().map(|(other, offset)| (*offset, velocities.get(other).copied().unwrap_or_default()));
+        inputs.extend(sense_flock(flock, config.flock_radius, config.bobble_speed));
+        // Smell isn't blocked by rocks or the dark, and outlasts a corpse's looks
+        let (corpse_direction, corpse_distance) = sense_nearest(position, &index.corpses, config.smell_range, wrap.0);
+        inputs.extend([corpse_direction.x, corpse_direction.y, corpse_distance]);
+        let (trail_direction, trail_level) = surroundings.pheromones.sense(position, wrap.0);
+        inputs.extend([trail_direction.x, trail_direction.y, trail_level]);
+        inputs.extend([
+            hunger.hunger / hunger.max_hunger,
+            energy.energy / energy.max_energy,
+        ]);
+        if THIRST_ENABLED {
+            let (water_direction, water_distance) = sense_nearest(position, &index.water, config.sense_range, wrap.0);
+            inputs.extend([
+                water_direction.x,
+                water_direction.y,
+                water_distance,
+                thirst.map_or(1., |t| t.thirst / t.max_thirst),
+            ]);
+        }
+        let outputs = brain.decide(&inputs);
+
+        let dt = time.delta_secs();
+        let speed = bobble.size_scaled(config.bobble_speed, config.size_speed);
+        let (velocity, facing) = act(&outputs, heading.0, &mut momentum.0, speed, &config, dt);
+        transform.translation += (velocity * dt).extend(0.);
+        if let Some(facing) = facing {
+            heading.0 = facing;
+        }
+    });
+}
+
+/// Turns a brain's outputs into the velocity to move at for the next `dt`
+/// seconds and, if it changed, the way to face, by the configured controls
+/// and movement model. `speed` is the bobble's top speed.
+pub fn act(outputs: &[f32], heading: Vec2, momentum: &mut Vec2, speed: f32, config: &SimConfig, dt: f32) -> (Vec2, Option<Vec2>) {
+    let (steer, facing) = match config.control_scheme {
+        ControlScheme::Direct => {
+            let steer = Vec2::new(outputs[0], outputs[1]);
+            (steer, steer.try_normalize())
+        }
+        ControlScheme::Tank => {
+            let turn = (outputs[1] * config.turn_rate * dt).to_radians();
+            let facing = Vec2::from_angle(turn).rotate(heading);
+            (facing * outputs[0], Some(facing))
+        }
+    };
+    let steer = match config.output_curve {
+        OutputCurve::Linear => steer,
+        OutputCurve::Squared => steer * steer.length(),
+    };
+    let velocity = match config.movement_model {
+        MovementModel::Direct => steer * speed,
+        MovementModel::Inertial => {
+            let accel = steer.clamp_length_max(1.) * config.max_accel;
+            let damped = (*momentum + accel * dt) * (1. - config.damping * dt).max(0.);
+            *momentum = damped.clamp_length_max(speed);
+            *momentum
+        }
+    };
+    (velocity, facing)
+}
+
+/// Direction to the nearest of `targets` and its distance as a fraction of
+/// `range`. Reads as no direction and far away when there are none.
+fn sense_nearest(position: Vec2, targets: &SpatialGrid<Entity>, range: f32, wrap: bool) -> (Vec2, f32) {
+    let nearest = targets.nearest(position, f32::INFINITY, wrap, |_| true).map(|(_, offset)| offset);
+
+    let direction = nearest.map_or(Vec2::ZERO, Vec2::normalize_or_zero);
+    let distance = nearest.map_or(1., |offset| (offset.length() / range).min(1.));
+    (direction, distance)
+}
+
+/// Where the `flock` of bobbles within `radius` is centred, as an offset in
+/// units of `radius`, and their mean velocity in units of `top_speed`, so
+/// cohesion, separation and alignment can all be learned. Each one is its
+/// offset and velocity. Reads as zeros when alone.
+fn sense_flock(flock: impl Iterator<Item = (Vec2, Vec2)>, radius: f32, top_speed: f32) -> [f32; FLOCK_INPUTS] {
+    let flock: Vec<(Vec2, Vec2)> = flock.collect();
+    if flock.is_empty() {
+        return [0.; FLOCK_INPUTS];
+    }
+    let count = flock.len();
+    let offset_sum: Vec2 = flock.iter().map(|(offset, _)| *offset).sum();
+    let velocity_sum: Vec2 = flock.iter().map(|(_, velocity)| *velocity).sum();
+    let center = offset_sum / count as f32 / radius;
+    let velocity = (velocity_sum / count as f32 / top_speed).clamp_length_max(1.);
+    [center.x, center.y, velocity.x, velocity.y]
+}
+
+/// Counts another step of life and rescores each bobble from its lifetime
+/// stats, so the weights can be tuned while the sim runs.
+pub fn update_fitness(config: Res<SimConfig>, mut bobbles: Query<(&mut Fitness, &mut LifetimeStats), With<Brain>>) {
+    for (mut fitness, mut stats) in bobbles.iter_mut() {
+        stats.ticks_alive += 1;
+        fitness.0 = stats.score(&config);
+    }
+}
@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+
+use rand::Rng;
+
+use crate::infection::{Infection, INFECTED_COLOR, INFECTED_TINT};
+use crate::species::SpeciesColor;
+use crate::{color_for_gender, Bobble, BobbleGender, Energy, Fitness};
+
+/// Colors for the low and high end of the metric-based color modes.
+const LOW_COLOR: Color = Color::srgb(1., 0.2, 0.2);
+const HIGH_COLOR: Color = Color::srgb(0.2, 1., 0.2);
+/// Most a child's hue can stray from its parents', in degrees.
+const HUE_DRIFT: f32 = 10.;
+/// In kin coloring, males are drawn darker and females lighter.
+const MALE_LIGHTNESS: f32 = 0.4;
+const FEMALE_LIGHTNESS: f32 = 0.7;
+/// In species coloring, bobbles that haven't been sorted into one yet.
+const UNSORTED_COLOR: Color = Color::srgb(0.6, 0.6, 0.6);
+
+/// What bobble sprite color encodes. Cycled with C.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq)]
+pub enum ColorMode {
+    #[default]
+    Gender,
+    /// A hue passed down from parent to child, so families show up as
+    /// clusters of similar colors. Lightness still tells the genders apart.
+    Kin,
+    /// One color per species of brain, so competing species can be told
+    /// apart. Bobbles born since the last sort show as unsorted.
+    Species,
+    Energy,
+    Fitness,
+    Age,
+}
+
+impl ColorMode {
+    fn next(self) -> Self {
+        match self {
+            ColorMode::Gender => ColorMode::Kin,
+            ColorMode::Kin => ColorMode::Species,
+            ColorMode::Species => ColorMode::Energy,
+            ColorMode::Energy => ColorMode::Fitness,
+            ColorMode::Fitness => ColorMode::Age,
+            ColorMode::Age => ColorMode::Gender,
+        }
+    }
+}
+
+/// The color a bobble shows under the current `ColorMode` when it isn't
+/// hovered. Hover observers restore this rather than a fixed spawn color.
+#[derive(Component)]
+pub struct BaseColor(pub Color);
+
+/// The hue, in degrees, a bobble was born with: a blend of its parents'
+/// with a little drift, or random for the first generation.
+#[derive(Debug, Clone, Copy)]
+pub struct KinHue(pub f32);
+
+impl KinHue {
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Self(rng.random_range(0.0..360.))
+    }
+
+    /// Halfway between two hues, the short way round the color wheel.
+    pub fn blend(self, other: Self) -> Self {
+        let difference = (other.0 - self.0 + 540.).rem_euclid(360.) - 180.;
+        Self((self.0 + difference / 2.).rem_euclid(360.))
+    }
+
+    /// Nudged by up to `HUE_DRIFT` either way.
+    pub fn drift(self, rng: &mut impl Rng) -> Self {
+        Self((self.0 + rng.random_range(-HUE_DRIFT..=HUE_DRIFT)).rem_euclid(360.))
+    }
+}
+
+/// Set while the pointer is over a bobble so recoloring leaves it alone.
+#[derive(Component)]
+pub struct Highlighted;
+
+pub fn cycle_color_mode(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut mode: ResMut<ColorMode>,
+) {
+    if kb_input.just_pressed(KeyCode::KeyC) {
+        *mode = mode.next();
+        println!("Coloring bobbles by {:?}", *mode);
+    }
+}
+
+pub fn update_bobble_colors(
+    mode: Res<ColorMode>,
+    mut bobbles: Query<(&Bobble, &Energy, Option<&Fitness>, Option<&SpeciesColor>, &mut BaseColor, &mut Sprite, Has<Highlighted>, Has<Infection>)>,
+) {
+    // Fitness and age have no natural maximum, so scale them to the current best
+    let max_fitness = bobbles.iter()
+        .filter_map(|(_, _, fitness, ..)| fitness.map(|f| f.0))
+        .fold(0., f64::max);
+    let max_age = bobbles.iter().map(|(bobble, ..)| bobble.age).fold(0., f32::max);
+
+    bobbles.iter_mut().for_each(|(bobble, energy, fitness, species, mut base_color, mut sprite, highlighted, infected)| {
+        base_color.0 = match *mode {
+            ColorMode::Gender => color_for_gender(&bobble.gender),
+            ColorMode::Kin => kin_color(bobble.traits.hue, bobble.gender),
+            ColorMode::Species => species.map_or(UNSORTED_COLOR, |s| s.color),
+            ColorMode::Energy => metric_color(energy.energy / energy.max_energy),
+            ColorMode::Fitness => metric_color(ratio(fitness.map_or(0., |f| f.0), max_fitness)),
+            ColorMode::Age => metric_color(ratio(bobble.age as f64, max_age as f64)),
+        };
+        // Sickness shows whatever the mode
+        if infected {
+            base_color.0 = base_color.0.mix(&INFECTED_COLOR, INFECTED_TINT);
+        }
+
+        if !highlighted {
+            sprite.color = base_color.0;
+        }
+    });
+}
+
+fn ratio(value: f64, max: f64) -> f32 {
+    if max > 0. { (value / max) as f32 } else { 0. }
+}
+
+fn metric_color(t: f32) -> Color {
+    LOW_COLOR.mix(&HIGH_COLOR, t.clamp(0., 1.))
+}
+
+fn kin_color(kin: KinHue, gender: BobbleGender) -> Color {
+    let lightness = match gender {
+        BobbleGender::Male => MALE_LIGHTNESS,
+        BobbleGender::Female => FEMALE_LIGHTNESS,
+    };
+    Color::hsl(kin.0, 0.8, lightness)
+}
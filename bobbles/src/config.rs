@@ -0,0 +1,481 @@
+use std::cmp::Ordering;
+use std::fs;
+use std::time::{Duration, Instant, SystemTime};
+
+use bevy::prelude::*;
+use neat::Float;
+use serde::{Deserialize, Serialize};
+
+use crate::extinction::ReseedSource;
+use crate::world::{SpawnPattern, WorldEdge, WorldWrap};
+
+/// Where the config is read from unless `BOBBLES_CONFIG` names another file.
+const CONFIG_PATH: &str = "bobbles.ron";
+
+/// Tuning values for the simulation, read from a RON file at startup so
+/// experiments don't need a rebuild. Fields missing from the file keep their
+/// defaults.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimConfig {
+    /// Seeds the simulation's randomness, so the same seed replays the same
+    /// run. None picks a fresh one each time.
+    pub seed: Option<u64>,
+    /// CSV file every bobble's lineage id, parents' ids and generation are
+    /// written to, for drawing family trees. Read at startup. None keeps no
+    /// log.
+    pub ancestry_log: Option<String>,
+    /// Bobbles and plants spawned at startup, and bobbles per reseed.
+    pub initial_spawn: usize,
+    pub plant_count: usize,
+    /// Ponds spawned at startup when thirst is on.
+    pub water_count: usize,
+    /// Random brains tried for each founder, spawned or reseeded from
+    /// scratch, on a short foraging trial; the best one is kept. 1 takes the
+    /// first untried.
+    pub foraging_candidates: usize,
+    /// Where a population that's died out gets its new brains from.
+    pub reseed_source: ReseedSource,
+    /// Seconds a bobble takes to shrink and fade away when it dies, before
+    /// its corpse appears. 0 skips straight to the corpse.
+    pub death_animation_time: f32,
+    /// Bobbles that start out infected. 0 means no disease.
+    pub initial_infections: usize,
+    /// Healthy bobbles within `infection_radius` of an infected one catch it
+    /// with `transmission_rate` chance per second, per infected neighbour.
+    pub infection_radius: f32,
+    pub transmission_rate: f64,
+    /// Health an infection drains per second, and the seconds until whoever
+    /// survives it recovers and becomes immune.
+    pub infection_damage: f32,
+    pub infection_recovery_time: f32,
+    /// Rocks spawned at startup, and how big they are.
+    pub obstacle_count: usize,
+    pub obstacle_radius: f32,
+    /// How far apart bobbles and plants start out, where there's room.
+    pub spawn_spacing: f32,
+    /// Where bobbles and plants appear, at the start and later on. Read at
+    /// startup.
+    pub bobble_spawn: SpawnPattern,
+    pub plant_spawn: SpawnPattern,
+    /// What happens to a bobble at the edge of the world, unless it wraps
+    /// round into a torus instead.
+    pub world_edge: WorldEdge,
+    pub world_wrap: bool,
+    /// Frames of recent positions drawn behind each bobble.
+    pub trail_length: usize,
+    /// What the bloom pulses with, the births per second that count as
+    /// full under `PulseMetric::BirthRate`, and the bloom intensity at the
+    /// metric's low and high end.
+    pub pulse_metric: PulseMetric,
+    pub pulse_birth_rate: f32,
+    pub bloom_min: f32,
+    pub bloom_max: f32,
+
+    /// Target movement speed factor.
+    pub target_speed: f32,
+    /// Top speed of a brain-driven bobble.
+    pub bobble_speed: f32,
+    /// Whether brains push bobbles around or set their velocity outright.
+    pub movement_model: MovementModel,
+    /// Under `MovementModel::Inertial`, the hardest push a brain can give,
+    /// in speed per second, and the fraction of its velocity a bobble loses
+    /// per second to drag. `bobble_speed` still caps the speed.
+    pub max_accel: f32,
+    pub damping: f32,
+    /// Whether the two movement outputs are an x/y direction or a throttle
+    /// and a turn, and under `ControlScheme::Tank` how fast a bobble can
+    /// turn, in degrees per second.
+    pub control_scheme: ControlScheme,
+    pub turn_rate: f32,
+    /// How the strength of a movement output maps onto speed (or push).
+    pub output_curve: OutputCurve,
+    /// How fast a bobble looking for a mate closes in on the nearest one.
+    pub mate_seek_speed: f32,
+    /// Distance at which senses read as "far away".
+    pub sense_range: f32,
+    /// How far the vision cone reaches.
+    pub vision_range: f32,
+    /// Width of the vision cone in degrees.
+    pub vision_fov: f32,
+    /// How far away other bobbles still count towards the flock sense.
+    pub flock_radius: f32,
+    /// Most neighbours that count towards it, nearest first. None counts
+    /// everyone within the radius.
+    pub flock_neighbours: Option<usize>,
+    /// How far away a corpse can be smelled.
+    pub smell_range: f32,
+    /// Cells per side of the pheromone grid over the world, the scent each
+    /// bobble leaves per second where it stands, and the fractions per second
+    /// that fade away and that even out with neighbouring cells.
+    pub pheromone_resolution: usize,
+    pub pheromone_deposit: f32,
+    pub pheromone_decay: f32,
+    pub pheromone_diffusion: f32,
+    /// Nutrition scavengers get from corpses and from plants, as multiples
+    /// of what anyone else would.
+    pub scavenger_corpse_gain: f32,
+    pub scavenger_plant_gain: f32,
+
+    /// Seconds from one noon to the next.
+    pub day_length: f32,
+    /// Fraction of the vision range left at midnight.
+    pub night_vision: f32,
+    /// How much faster hunger drains at midnight.
+    pub night_hunger: f32,
+    /// Fraction of a sprite's half-width taken up by the body drawn in it.
+    /// Two things touch, to eat or mate, once their bodies overlap.
+    pub collision_scale: f32,
+
+    /// Ranges, as (min, max), that founders' maximum hunger, health and
+    /// energy are drawn from. Children, which take after their parents, are
+    /// kept within them too.
+    pub max_hunger_range: (f32, f32),
+    pub max_health_range: (f32, f32),
+    pub max_energy_range: (f32, f32),
+
+    /// How much hunger decreases every second.
+    pub hunger_rate: f32,
+    /// Extra hunger lost every second per node and enabled connection in a
+    /// bobble's brain, so needlessly big brains are selected against.
+    pub metabolism_cost: f32,
+    pub healing_rate: f32,
+    /// Energy used per second while moving, and regained per second while
+    /// rested, so a still bobble refills in `start_resting_time` plus
+    /// `max_energy / energy_rate` seconds.
+    pub energy_rate: f32,
+    /// Grace period: seconds a bobble has to go fed and without taking any
+    /// damage (starving, poison, thirst) before health starts coming back.
+    pub start_healing_time: f32,
+    /// Seconds without moving before energy starts coming back.
+    pub start_resting_time: f32,
+
+    /// How much body size changes a bobble's maximum health, hunger rate,
+    /// energy cost of moving and top speed: the fraction each one changes
+    /// by per unit of size above 1, so 0.5 makes a size 1.2 bobble's 10%
+    /// higher. Negative values make bigger bobbles lower.
+    pub size_health: f32,
+    pub size_hunger: f32,
+    pub size_energy: f32,
+    pub size_speed: f32,
+
+    /// Whether bobbles pair up to have children or bud them off alone.
+    pub reproduction_mode: ReproductionMode,
+    pub pregnancy_time: f32,
+    /// How much faster hunger and energy drain while pregnant.
+    pub pregnancy_drain: f32,
+    /// A bobble looks for a mate once it's at least this many seconds old
+    /// and its hunger and energy are at least these fractions of their
+    /// maximum.
+    pub mating_min_age: f32,
+    pub mating_min_hunger: f32,
+    pub mating_min_energy: f32,
+    /// Seconds after mating before either parent can mate again.
+    pub mating_cooldown: f32,
+    /// In asexual mode, seconds before a bobble can bud again, and the
+    /// fraction of its maximum hunger and energy each bud costs.
+    pub budding_cooldown: f32,
+    pub budding_cost: f32,
+    /// Chance each bobble is male, for the first generation and for children
+    /// under `SexDetermination::Random`.
+    pub male_ratio: f64,
+    /// What decides a child's sex, and under `SexDetermination::Temperature`
+    /// how far the male chance swings above `male_ratio` at noon and below it
+    /// at midnight.
+    pub sex_determination: SexDetermination,
+    pub temperature_sex_bias: f64,
+    /// Seconds between sorting the living bobbles into species, and the
+    /// compatibility distance under which two brains count as one species.
+    pub speciation_interval: f32,
+    pub species_threshold: Float,
+    /// What each part of a bobble's lifetime record is worth in its fitness:
+    /// per unit of nutrition eaten, per unit of distance walked, per child
+    /// conceived, per second alive and per grid cell visited.
+    pub fitness_food: f64,
+    pub fitness_distance: f64,
+    pub fitness_offspring: f64,
+    pub fitness_survival: f64,
+    pub fitness_exploration: f64,
+    /// Side of the grid cells exploration is counted in. Pacing back and
+    /// forth within one cell earns nothing.
+    pub exploration_cell_size: f32,
+}
+
+/// How brain outputs turn into movement.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MovementModel {
+    /// Outputs are the velocity, so bobbles turn and stop instantly.
+    Direct,
+    /// Outputs are an acceleration, so bobbles carry momentum and coast.
+    Inertial,
+}
+
+/// What a brain's two movement outputs mean.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ControlScheme {
+    /// Outputs are the x and y of the way to move.
+    Direct,
+    /// One output drives forward (or back) along the heading, the other
+    /// turns it left or right.
+    Tank,
+}
+
+/// How an output's strength, from 0 to 1, becomes a fraction of full speed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum OutputCurve {
+    Linear,
+    /// Weak outputs give much less than their share, so small nudges don't
+    /// send a bobble off at speed.
+    Squared,
+}
+
+/// What the camera's bloom says about the ecosystem.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PulseMetric {
+    /// How rested the brain-driven bobbles are on average.
+    Energy,
+    /// How fast children have been born lately.
+    BirthRate,
+}
+
+/// How new bobbles come about.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ReproductionMode {
+    /// A male and a female mate and she carries the child.
+    Sexual,
+    /// Any bobble that qualifies buds off a mutated clone of itself.
+    Asexual,
+}
+
+/// How a child's sex is decided.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SexDetermination {
+    /// Male with chance `male_ratio`.
+    Random,
+    /// Male with chance `male_ratio` plus a bias inherited from the parents,
+    /// which drifts a little each generation, so the ratio itself can evolve.
+    Inherited,
+    /// Warmth decides: children born by day are more often male, by night
+    /// more often female.
+    Temperature,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            ancestry_log: None,
+            initial_spawn: 20,
+            plant_count: 20,
+            water_count: 5,
+            foraging_candidates: 1,
+            reseed_source: ReseedSource::Survivors,
+            death_animation_time: 1.5,
+            initial_infections: 0,
+            infection_radius: 30.,
+            transmission_rate: 0.5,
+            infection_damage: 2.,
+            infection_recovery_time: 20.,
+            obstacle_count: 6,
+            obstacle_radius: 40.,
+            spawn_spacing: 40.,
+            bobble_spawn: SpawnPattern::Uniform,
+            plant_spawn: SpawnPattern::Uniform,
+            world_edge: WorldEdge::Clamp,
+            world_wrap: false,
+            trail_length: 60,
+            pulse_metric: PulseMetric::Energy,
+            pulse_birth_rate: 0.5,
+            bloom_min: 0.05,
+            bloom_max: 0.4,
+            target_speed: 200.,
+            bobble_speed: 100.,
+            movement_model: MovementModel::Inertial,
+            max_accel: 400.,
+            damping: 2.,
+            control_scheme: ControlScheme::Direct,
+            turn_rate: 180.,
+            output_curve: OutputCurve::Linear,
+            mate_seek_speed: 60.,
+            sense_range: 500.,
+            vision_range: 300.,
+            vision_fov: 120.,
+            flock_radius: 150.,
+            flock_neighbours: None,
+            smell_range: 500.,
+            pheromone_resolution: 50,
+            pheromone_deposit: 1.,
+            pheromone_decay: 0.1,
+            pheromone_diffusion: 0.5,
+            scavenger_corpse_gain: 1.5,
+            scavenger_plant_gain: 0.5,
+            day_length: 120.,
+            night_vision: 0.4,
+            night_hunger: 1.3,
+            collision_scale: 0.3,
+            max_hunger_range: (50., 200.),
+            max_health_range: (50., 200.),
+            max_energy_range: (100., 150.),
+            hunger_rate: 1.,
+            metabolism_cost: 0.005,
+            healing_rate: 0.2,
+            energy_rate: 0.2,
+            start_healing_time: 10.,
+            start_resting_time: 2.,
+            size_health: 1.,
+            size_hunger: 1.,
+            size_energy: 0.5,
+            size_speed: -0.5,
+            reproduction_mode: ReproductionMode::Sexual,
+            pregnancy_time: 20.,
+            pregnancy_drain: 1.5,
+            mating_min_age: 10.,
+            mating_min_hunger: 0.6,
+            mating_min_energy: 0.6,
+            mating_cooldown: 30.,
+            budding_cooldown: 30.,
+            budding_cost: 0.4,
+            male_ratio: 0.5,
+            sex_determination: SexDetermination::Random,
+            temperature_sex_bias: 0.3,
+            speciation_interval: 5.,
+            species_threshold: 3.,
+            fitness_food: 0.5,
+            fitness_distance: 0.,
+            fitness_offspring: 20.,
+            fitness_survival: 1.,
+            fitness_exploration: 1.,
+            exploration_cell_size: 50.,
+        }
+    }
+}
+
+impl SimConfig {
+    pub fn path() -> String {
+        std::env::var("BOBBLES_CONFIG").unwrap_or_else(|_| CONFIG_PATH.to_string())
+    }
+
+    /// Reads the config file, falling back to the defaults if it's missing or
+    /// doesn't parse.
+    pub fn load() -> Self {
+        let path = Self::path();
+        let Ok(text) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match ron::from_str(&text) {
+            Ok(config) => {
+                println!("Loaded config from {}", path);
+                Self::check_ranges(config)
+            }
+            Err(e) => {
+                println!("Couldn't parse {}, using defaults: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Puts back the default for any range whose min is above its max, so
+    /// nothing drawn from it can panic.
+    fn check_ranges(mut config: Self) -> Self {
+        let defaults = Self::default();
+        for (name, range, default) in [
+            ("max_hunger_range", &mut config.max_hunger_range, defaults.max_hunger_range),
+            ("max_health_range", &mut config.max_health_range, defaults.max_health_range),
+            ("max_energy_range", &mut config.max_energy_range, defaults.max_energy_range),
+        ] {
+            // NaN bounds don't compare at all, and are caught too
+            if range.0.partial_cmp(&range.1).is_none_or(Ordering::is_gt) {
+                println!("{} has its min above its max, using {:?}", name, default);
+                *range = default;
+            }
+        }
+        config
+    }
+}
+
+/// How often the config file is checked for changes, in wall-clock time so
+/// headless runs don't poll every step.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Modification time of the config file when it was last read.
+#[derive(Resource)]
+pub struct ConfigWatch {
+    modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+impl Default for ConfigWatch {
+    fn default() -> Self {
+        Self {
+            modified: modified_time(&SimConfig::path()),
+            last_check: Instant::now(),
+        }
+    }
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Reloads `SimConfig` when its file changes. Rates, speeds and timers are
+/// read every step so they apply straight away, and so do the world's edges
+/// and where reseeded brains come from; founder counts apply from the next
+/// reseed. The seed, the ancestry log, the starting infections and plant,
+/// pond, obstacle and spawn settings are only used at startup, so changing
+/// them just logs that a restart is needed.
+pub fn reload_config(
+    mut watch: ResMut<ConfigWatch>,
+    mut config: ResMut<SimConfig>,
+    mut edge: ResMut<WorldEdge>,
+    mut wrap: ResMut<WorldWrap>,
+    mut reseed_source: ResMut<ReseedSource>,
+) {
+    if watch.last_check.elapsed() < WATCH_INTERVAL {
+        return;
+    }
+    watch.last_check = Instant::now();
+
+    let path = SimConfig::path();
+    let modified = modified_time(&path);
+    if modified.is_none() || modified == watch.modified {
+        return;
+    }
+    watch.modified = modified;
+
+    let Ok(text) = fs::read_to_string(&path) else {
+        return;
+    };
+    let new: SimConfig = match ron::from_str(&text) {
+        Ok(new) => SimConfig::check_ranges(new),
+        Err(e) => {
+            println!("Couldn't parse {}, keeping current config: {}", path, e);
+            return;
+        }
+    };
+    if new == *config {
+        return;
+    }
+
+    if new.plant_count != config.plant_count
+        || new.water_count != config.water_count
+        || new.obstacle_count != config.obstacle_count
+        || new.obstacle_radius != config.obstacle_radius
+        || new.bobble_spawn != config.bobble_spawn
+        || new.plant_spawn != config.plant_spawn
+        || new.spawn_spacing != config.spawn_spacing
+        || new.initial_infections != config.initial_infections
+    {
+        println!("Plant, water, obstacle, spawn and starting infection settings in {} take effect after a restart", path);
+    }
+    if new.seed != config.seed || new.ancestry_log != config.ancestry_log {
+        println!("The seed and ancestry log in {} take effect after a restart", path);
+    }
+    if new.initial_spawn != config.initial_spawn || new.foraging_candidates != config.foraging_candidates {
+        println!("Founder counts in {} take effect from the next reseed", path);
+    }
+    println!("Reloaded config from {}", path);
+    *edge = new.world_edge;
+    wrap.0 = new.world_wrap;
+    *reseed_source = new.reseed_source;
+    *config = new;
+}
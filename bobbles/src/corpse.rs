@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+
+use crate::{Collider, Edible, PLAYER_SCALE};
+
+const CORPSE_COLOR: Color = Color::srgb(0.35, 0.3, 0.3);
+/// Nutrition a corpse is worth per point of its owner's max health, so bigger
+/// bobbles leave more food behind.
+const CORPSE_NUTRITION_PER_HEALTH: f32 = 0.5;
+/// How long an uneaten corpse lies around before it's gone. Its nutrition
+/// rots away over that time, so whoever gets there first eats best.
+const CORPSE_DECAY_TIME: f32 = 30.;
+/// Fraction of the decay time the corpse stays visible for. Its smell lasts
+/// the whole time.
+const CORPSE_VISIBLE_FRACTION: f32 = 0.5;
+
+#[derive(Component)]
+pub struct Corpse {
+    pub timer: Timer,
+    fresh_nutrition: f32,
+}
+
+/// What's left on screen of a bobble that just died: a copy of its sprite
+/// that shrinks and fades away, then leaves a corpse. The bobble itself is
+/// already gone, so nothing else in the sim sees it.
+#[derive(Component)]
+pub struct Dying {
+    timer: Timer,
+    max_health: f32,
+    color: Color,
+}
+
+/// Starts the death animation for a bobble that died at `transform` looking
+/// like `sprite`.
+pub fn spawn_dying(commands: &mut Commands, sprite: &Sprite, transform: &Transform, max_health: f32, duration: f32) {
+    commands.spawn((
+        Dying {
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+            max_health,
+            color: sprite.color,
+        },
+        sprite.clone(),
+        *transform,
+    ));
+}
+
+/// Shrinks and fades each dying bobble, swapping it for its corpse once the
+/// animation is over.
+pub fn animate_dying(
+    time: Res<Time>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut dying: Query<(Entity, &mut Dying, &mut Transform, &mut Sprite)>,
+) {
+    dying.iter_mut().for_each(|(entity, mut dying, mut transform, mut sprite)| {
+        if dying.timer.tick(time.delta()).just_finished() {
+            spawn_corpse(&mut commands, &asset_server, transform.translation.truncate(), dying.max_health);
+            commands.entity(entity).despawn();
+            return;
+        }
+        let remaining = dying.timer.fraction_remaining();
+        transform.scale = Vec3::splat(remaining);
+        sprite.color = dying.color.with_alpha(remaining);
+    });
+}
+
+/// Leaves an edible corpse where a bobble died.
+pub fn spawn_corpse(commands: &mut Commands, asset_server: &AssetServer, position: Vec2, max_health: f32) {
+    commands.spawn((
+        Corpse {
+            timer: Timer::from_seconds(CORPSE_DECAY_TIME, TimerMode::Once),
+            fresh_nutrition: max_health * CORPSE_NUTRITION_PER_HEALTH,
+        },
+        Edible {
+            nutrition_value: max_health * CORPSE_NUTRITION_PER_HEALTH,
+        },
+        Collider,
+        Sprite {
+            image: asset_server.load("human.png"),
+            color: CORPSE_COLOR,
+            custom_size: Some(Vec2::splat(PLAYER_SCALE * 0.75)),
+            ..default()
+        },
+        Transform::from_translation(position.extend(0.)),
+    ));
+}
+
+/// Rots corpses: their nutrition falls towards nothing and they fade from
+/// sight, until they're gone altogether.
+pub fn decay_corpses(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut corpses: Query<(Entity, &mut Corpse, &mut Edible, &mut Sprite)>,
+) {
+    corpses.iter_mut().for_each(|(entity, mut corpse, mut edible, mut sprite)| {
+        if corpse.timer.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn();
+            return;
+        }
+        let remaining = corpse.timer.fraction_remaining();
+        edible.nutrition_value = corpse.fresh_nutrition * remaining;
+        let visible = (1. - corpse.timer.fraction() / CORPSE_VISIBLE_FRACTION).max(0.);
+        sprite.color = CORPSE_COLOR.with_alpha(visible);
+    });
+}
@@ -0,0 +1,53 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::config::SimConfig;
+use crate::{WorldPlane, WORLD_COLOR};
+
+/// How dark the world plane gets at midnight, as a fraction of its day color.
+const NIGHT_BRIGHTNESS: f32 = 0.25;
+
+/// Simulated seconds since the sim started, which sets the time of day.
+#[derive(Resource, Default)]
+pub struct GameTime {
+    pub elapsed: f32,
+}
+
+impl GameTime {
+    /// How far through the current day it is, from 0 (noon) to 1 (the next noon).
+    pub fn phase(&self, day_length: f32) -> f32 {
+        (self.elapsed / day_length.max(f32::EPSILON)).fract()
+    }
+
+    /// 1 at noon, 0 at midnight, easing smoothly between the two.
+    pub fn daylight(&self, day_length: f32) -> f32 {
+        0.5 + 0.5 * (self.phase(day_length) * TAU).cos()
+    }
+
+    /// `night` at midnight, 1 at noon, in between otherwise. For scaling
+    /// values that change with the light.
+    pub fn scale(&self, config: &SimConfig, night: f32) -> f32 {
+        night + (1. - night) * self.daylight(config.day_length)
+    }
+}
+
+pub fn advance_game_time(time: Res<Time>, mut game_time: ResMut<GameTime>) {
+    game_time.elapsed += time.delta_secs();
+}
+
+/// Darkens the world plane as night falls.
+pub fn tint_world(
+    config: Res<SimConfig>,
+    game_time: Res<GameTime>,
+    world: Query<&MeshMaterial2d<ColorMaterial>, With<WorldPlane>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let brightness = game_time.scale(&config, NIGHT_BRIGHTNESS);
+    let day = WORLD_COLOR.to_srgba();
+    for handle in &world {
+        if let Some(material) = materials.get_mut(&handle.0) {
+            material.color = Color::srgb(day.red * brightness, day.green * brightness, day.blue * brightness);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+/// Events kept before the oldest are dropped.
+const EVENT_CAPACITY: usize = 200;
+/// Lines shown in the on-screen feed.
+const FEED_LINES: usize = 8;
+
+/// A running log of notable things happening in the sim (births, deaths,
+/// mating, poisonings), newest last. Systems `push` into it as they go.
+#[derive(Resource, Default)]
+pub struct SimEvents {
+    events: VecDeque<(f32, String)>,
+    /// Sim time stamped onto new events, kept current by `stamp_events`.
+    now: f32,
+}
+
+impl SimEvents {
+    pub fn push(&mut self, text: impl Into<String>) {
+        if self.events.len() >= EVENT_CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back((self.now, text.into()));
+    }
+
+    /// The last `count` events, oldest first.
+    pub fn recent(&self, count: usize) -> impl Iterator<Item = &(f32, String)> {
+        self.events.iter().skip(self.events.len().saturating_sub(count))
+    }
+}
+
+/// Keeps the time events are stamped with up to date, so systems pushing
+/// events don't each need the clock.
+pub fn stamp_events(time: Res<Time>, mut events: ResMut<SimEvents>) {
+    // Not an event itself, so don't wake up the feed
+    events.bypass_change_detection().now = time.elapsed_secs();
+}
+
+/// The text panel showing the latest events.
+#[derive(Component)]
+pub struct EventFeed;
+
+pub fn setup_event_feed(mut commands: Commands) {
+    commands.spawn((
+        EventFeed,
+        Text::new(""),
+        TextFont {
+            font_size: 14.,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: px(12),
+            right: px(12),
+            ..default()
+        },
+    ));
+}
+
+pub fn update_event_feed(events: Res<SimEvents>, mut feed: Single<&mut Text, With<EventFeed>>) {
+    if !events.is_changed() {
+        return;
+    }
+    let lines: Vec<String> = events.recent(FEED_LINES)
+        .map(|(time, text)| format!("[{:.0}s] {}", time, text))
+        .collect();
+    **feed = Text::new(lines.join("\n"));
+}
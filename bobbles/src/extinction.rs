@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use neat::{Float, Genome, WeightInit};
+use rand::seq::IndexedRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::brain::{Brain, Innovations};
+use crate::config::SimConfig;
+use crate::foraging::fittest_random_brain;
+use crate::hall_of_fame::HallOfFame;
+use crate::sim_rng::SimRng;
+use crate::spatial::SpatialGrid;
+use crate::world::SpawnLayout;
+use crate::{
+    spawn_bobble, Bobble, BobbleGender, BobbleTraits, Fitness, Health, Target, MUTATION_RATES, RECURRENT_CONNECTIONS,
+    WEIGHT_CAP,
+};
+
+/// Where the brains of a reseeded population come from. Set by the config's
+/// `reseed_source`.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ReseedSource {
+    /// Fresh minimal genomes, as at startup.
+    Random,
+    /// Mutated copies of the last bobbles to die, the fitter ones more often.
+    /// Falls back to random genomes if none were recorded.
+    Survivors,
+    /// Mutated copies of the best genomes of the whole run. Falls back to
+    /// random genomes if the hall of fame is empty.
+    HallOfFame,
+}
+
+impl FromWorld for ReseedSource {
+    fn from_world(world: &mut World) -> Self {
+        world.resource::<SimConfig>().reseed_source
+    }
+}
+
+/// The most recent deaths and how many times the population has died out.
+#[derive(Resource, Default)]
+pub struct Extinctions {
+    survivors: VecDeque<Genome>,
+    highest_generation: u32,
+    pub count: u32,
+}
+
+/// Remembers the genomes of bobbles about to be despawned. Runs before
+/// `despawn_dead` so the brains are still around.
+pub fn record_fallen(
+    config: Res<SimConfig>,
+    mut extinctions: ResMut<Extinctions>,
+    bobbles: Query<(&Bobble, &Health, &Brain, &Fitness), Without<Target>>,
+) {
+    for (bobble, health, brain, fitness) in bobbles.iter() {
+        if health.alive {
+            continue;
+        }
+        let mut genome = brain.genome.clone();
+        genome.fitness = fitness.0 as Float;
+        extinctions.survivors.push_back(genome);
+        while extinctions.survivors.len() > config.initial_spawn {
+            extinctions.survivors.pop_front();
+        }
+        extinctions.highest_generation = extinctions.highest_generation.max(bobble.generation);
+    }
+}
+
+/// Spawns a new population once every brain-driven bobble is gone.
+pub fn reseed_on_extinction(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<SimConfig>,
+    source: Res<ReseedSource>,
+    mut extinctions: ResMut<Extinctions>,
+    mut innovations: ResMut<Innovations>,
+    hall: Res<HallOfFame>,
+    layout: Res<SpawnLayout>,
+    bobbles: Query<(), (With<Bobble>, With<Brain>)>,
+    mut rng: ResMut<SimRng>,
+) {
+    if !bobbles.is_empty() {
+        return;
+    }
+
+    extinctions.count += 1;
+    println!(
+        "Extinction #{} after reaching generation {}, reseeding from {:?}",
+        extinctions.count, extinctions.highest_generation, *source
+    );
+
+    let rng = &mut rng.0;
+    let survivors: Vec<Genome> = extinctions.survivors.drain(..).collect();
+    let parents = match *source {
+        ReseedSource::Random => &[][..],
+        ReseedSource::Survivors => &survivors[..],
+        ReseedSource::HallOfFame => hall.genomes(),
+    };
+    let mut placed = SpatialGrid::new(config.spawn_spacing);
+    for _ in 0..config.initial_spawn {
+        let brain = match parents {
+            [] => fittest_random_brain(config.foraging_candidates, &mut innovations, &config, rng),
+            _ => {
+                let mut genome = pick_parent(parents, rng).clone();
+                genome.fitness = 0.;
+                genome.mutate_with_rng(&mut innovations, &MUTATION_RATES, WEIGHT_CAP, RECURRENT_CONNECTIONS, WeightInit::default(), rng);
+                Brain::new(genome)
+            }
+        };
+
+        spawn_bobble(
+            &mut commands,
+            &asset_server,
+            &config,
+            layout.bobbles.sample_spaced(&mut placed, config.spawn_spacing, rng),
+            Bobble::new(BobbleGender::random(config.male_ratio, rng), 0, BobbleTraits::random(&config, rng)),
+            brain,
+        );
+    }
+    extinctions.highest_generation = 0;
+}
+
+/// One of `parents`, picked with probability proportional to its fitness, or
+/// uniformly if none of them scored anything.
+fn pick_parent<'a>(parents: &'a [Genome], rng: &mut impl Rng) -> &'a Genome {
+    parents.choose_weighted(rng, |genome| genome.fitness.max(0.))
+        .or_else(|_| parents.choose(rng).ok_or(()))
+        .expect("parents isn't empty")
+}
@@ -0,0 +1,100 @@
+use std::ops::RangeInclusive;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::config::SimConfig;
+
+/// Share of plants that grow poisonous.
+pub const POISON_CHANCE: f64 = 0.15;
+/// Poisonous plants only fill this fraction of what their type normally does.
+const POISON_NUTRITION: f32 = 0.2;
+/// Health lost by eating a poisonous plant.
+const POISON_DAMAGE: f32 = 25.;
+const POISON_TINT: Color = Color::srgb(0.6, 0.1, 0.7);
+
+/// What kind of plant something is. Decides how filling it is and how it looks.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub enum FoodType {
+    Berry,
+    Leaf,
+    Fungus,
+}
+
+impl FoodType {
+    pub fn random(rng: &mut impl Rng) -> Self {
+        match rng.random_range(0..3) {
+            0 => FoodType::Berry,
+            1 => FoodType::Leaf,
+            _ => FoodType::Fungus,
+        }
+    }
+
+    /// Hunger restored by eating one, drawn fresh every time a plant grows.
+    pub fn nutrition_range(self) -> RangeInclusive<f32> {
+        match self {
+            FoodType::Berry => 60.0..=100.0,
+            FoodType::Leaf => 20.0..=50.0,
+            FoodType::Fungus => 100.0..=160.0,
+        }
+    }
+
+    pub fn roll_nutrition(self, rng: &mut impl Rng) -> f32 {
+        rng.random_range(self.nutrition_range())
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            FoodType::Berry => Color::srgb(0.9, 0.1, 0.3),
+            FoodType::Leaf => Color::srgb(0., 1., 0.),
+            FoodType::Fungus => Color::srgb(0.8, 0.6, 0.3),
+        }
+    }
+}
+
+/// What a bobble eats best. Inherited from one parent or the other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Diet {
+    /// Gets the same from plants and corpses.
+    Omnivore,
+    /// Gets more from corpses and less from plants.
+    Scavenger,
+}
+
+impl Diet {
+    pub fn random(rng: &mut impl Rng) -> Self {
+        if rng.random_bool(0.5) { Diet::Omnivore } else { Diet::Scavenger }
+    }
+
+    /// Multiple of an edible's nutrition this diet gets out of it.
+    pub fn gain(self, config: &SimConfig, is_corpse: bool) -> f32 {
+        match (self, is_corpse) {
+            (Diet::Omnivore, _) => 1.,
+            (Diet::Scavenger, true) => config.scavenger_corpse_gain,
+            (Diet::Scavenger, false) => config.scavenger_plant_gain,
+        }
+    }
+}
+
+/// Marks a plant that hurts whoever eats it. Only tints the plant a little, so
+/// telling it apart takes a closer look.
+#[derive(Component)]
+pub struct Poisonous {
+    pub damage: f32,
+}
+
+impl Poisonous {
+    pub fn roll(rng: &mut impl Rng) -> Option<Self> {
+        rng.random_bool(POISON_CHANCE).then_some(Self { damage: POISON_DAMAGE })
+    }
+}
+
+/// Nutrition of a freshly grown plant.
+pub fn plant_nutrition(food_type: FoodType, poisonous: bool, rng: &mut impl Rng) -> f32 {
+    let nutrition = food_type.roll_nutrition(rng);
+    if poisonous { nutrition * POISON_NUTRITION } else { nutrition }
+}
+
+pub fn plant_color(food_type: FoodType, poisonous: bool) -> Color {
+    if poisonous { food_type.color().mix(&POISON_TINT, 0.25) } else { food_type.color() }
+}
@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use neat::Genome;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::brain::{act, Brain, Innovations, BRAIN_INPUTS};
+use crate::config::SimConfig;
+use crate::food::FoodType;
+use crate::thirst::THIRST_ENABLED;
+use crate::vision::{VisionCone, VISION_BINS, VISION_SEES_BOBBLES};
+use crate::{LifetimeStats, PLANT_SCALE, PLAYER_SCALE, SIM_STEP};
+
+/// Steps of `SIM_STEP` a trial runs for, if the forager doesn't starve first.
+const FORAGING_STEPS: usize = 1200;
+/// How far into the trial a forager that never eats starves. Hunger drains
+/// faster than the config's `hunger_rate` when that's what it takes, or a
+/// trial this short could never tell a forager from one that just sits still.
+const STARVATION_POINT: f32 = 0.5;
+/// Plants on offer at any one time, somewhere in a square of `ARENA_SIZE`
+/// around where the forager starts. Each one eaten regrows elsewhere in it.
+const FOOD_COUNT: usize = 12;
+const ARENA_SIZE: f32 = 500.;
+/// What the forager starts with and can hold, as a middling bobble would.
+const MAX_HUNGER: f32 = 100.;
+
+/// Scores `genome` as a bobble brain on a small foraging task, run without
+/// Bevy: one forager alone in an open arena, with plants scripted by `seed`,
+/// so every genome given the same seed faces the same plants. The score
+/// weighs what it did by the config's fitness weights, as in the live world,
+/// so brains good here should be good founders there.
+pub fn foraging_fitness(genome: &Genome, config: &SimConfig, seed: u64) -> f64 {
+    let mut brain = Brain::new(genome.clone());
+    forage(config, seed, |position, heading, hunger, plants| {
+        brain.decide(&senses(position, heading, hunger, plants, config))
+    })
+}
+
+/// Hunger lost per second on a trial.
+fn trial_hunger_rate(config: &SimConfig) -> f32 {
+    let starve_after = FORAGING_STEPS as f32 * SIM_STEP as f32 * STARVATION_POINT;
+    config.hunger_rate.max(MAX_HUNGER / starve_after)
+}
+
+/// Runs one trial and scores it. Each step `think` gets where the forager is,
+/// which way it faces, its hunger and the plants, and gives back what its
+/// brain's outputs would be.
+fn forage(
+    config: &SimConfig,
+    seed: u64,
+    mut think: impl FnMut(Vec2, Vec2, f32, &[(Vec2, f32)]) -> Vec<f32>,
+) -> f64 {
+    let mut plants_rng = StdRng::seed_from_u64(seed);
+    let grow = |rng: &mut StdRng| {
+        let position = Vec2::new(
+            rng.random_range(-ARENA_SIZE / 2.0..=ARENA_SIZE / 2.0),
+            rng.random_range(-ARENA_SIZE / 2.0..=ARENA_SIZE / 2.0),
+        );
+        (position, FoodType::random(rng).roll_nutrition(rng))
+    };
+    let mut plants: Vec<(Vec2, f32)> = (0..FOOD_COUNT).map(|_| grow(&mut plants_rng)).collect();
+
+    let dt = SIM_STEP as f32;
+    let reach = (PLAYER_SCALE + PLANT_SCALE) / 2. * config.collision_scale;
+    let mut position = Vec2::ZERO;
+    let mut heading = Vec2::X;
+    let mut momentum = Vec2::ZERO;
+    let mut hunger = MAX_HUNGER;
+    let mut stats = LifetimeStats::default();
+
+    for _ in 0..FORAGING_STEPS {
+        let outputs = think(position, heading, hunger, &plants);
+        let (velocity, facing) = act(&outputs, heading, &mut momentum, config.bobble_speed, config, dt);
+        position += velocity * dt;
+        if let Some(facing) = facing {
+            heading = facing;
+        }
+
+        stats.ticks_alive += 1;
+        stats.distance_traveled += (velocity * dt).length();
+        let cell = (position / config.exploration_cell_size.max(1.)).floor();
+        stats.visited_cells.insert((cell.x as i32, cell.y as i32));
+
+        for plant in plants.iter_mut() {
+            if plant.0.distance(position) < reach {
+                hunger = (hunger + plant.1).min(MAX_HUNGER);
+                stats.food_eaten += plant.1;
+                *plant = grow(&mut plants_rng);
+            }
+        }
+        hunger -= trial_hunger_rate(config) * dt;
+        if hunger <= 0. {
+            break;
+        }
+    }
+    stats.score(config)
+}
+
+/// The forager's inputs, laid out as `BRAIN_INPUTS` describes. It sees the
+/// plants, and everything else reads as it would with nothing else about:
+/// no rocks, bobbles, corpses, scent or water, and never tired or thirsty.
+fn senses(position: Vec2, heading: Vec2, hunger: f32, plants: &[(Vec2, f32)], config: &SimConfig) -> Vec<f32> {
+    let cone = VisionCone {
+        position,
+        heading,
+        range: config.vision_range,
+        fov: config.vision_fov,
+        wrap: false,
+    };
+    let mut inputs = vec![1.]; // Bias
+    inputs.extend(cone.sense(plants.iter().map(|(p, _)| *p), &[]));
+    inputs.extend([1.; VISION_BINS]);
+    if VISION_SEES_BOBBLES {
+        inputs.extend([1.; VISION_BINS]);
+    }
+    inputs.extend([0.; 4]); // Flock
+    inputs.extend([0., 0., 1.]); // Corpse smell
+    inputs.extend([0., 0., 0.]); // Pheromones
+    inputs.extend([hunger / MAX_HUNGER, 1.]);
+    if THIRST_ENABLED {
+        inputs.extend([0., 0., 1., 1.]);
+    }
+    debug_assert_eq!(inputs.len(), BRAIN_INPUTS);
+    inputs
+}
+
+/// The best by `foraging_fitness` of `candidates` fresh random brains, all
+/// tried on the same plants, for founders that start out competent. With
+/// only one there's nothing to choose, so it isn't tried at all.
+pub fn fittest_random_brain(
+    candidates: usize,
+    innovations: &mut Innovations,
+    config: &SimConfig,
+    rng: &mut impl Rng,
+) -> Brain {
+    if candidates <= 1 {
+        return Brain::random(innovations, rng);
+    }
+    let seed = rng.random();
+    (0..candidates)
+        .map(|_| Brain::random(innovations, rng))
+        .map(|brain| (foraging_fitness(&brain.genome, config, seed), brain))
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, brain)| brain)
+        .expect("there's at least one candidate")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_forager_that_heads_for_food_beats_one_that_sits_still() {
+        let config = SimConfig::default();
+        let sitter = forage(&config, 1, |_, _, _, _| vec![0.; 2]);
+        let forager = forage(&config, 1, |position, _, _, plants| {
+            let nearest = plants.iter()
+                .map(|(plant, _)| *plant - position)
+                .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+                .unwrap_or_default();
+            let steer = nearest.normalize_or_zero();
+            vec![steer.x, steer.y]
+        });
+        assert!(forager > sitter, "{forager} <= {sitter}");
+
+        // Sitting still starves partway through instead of surviving the lot
+        let full_trial = config.fitness_survival * FORAGING_STEPS as f64 * SIM_STEP;
+        assert!(sitter < full_trial, "{sitter}");
+    }
+}
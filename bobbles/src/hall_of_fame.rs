@@ -0,0 +1,101 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use neat::{Float, NodeType};
+
+use crate::brain::{Brain, BRAIN_INPUTS, BRAIN_OUTPUTS};
+use crate::config::SimConfig;
+use crate::sim_rng::SimRng;
+use crate::{spawn_bobble, Bobble, BobbleGender, BobbleTraits, Fitness, Health, Target};
+
+/// How many of the best genomes of the run are kept.
+const HALL_OF_FAME_SIZE: usize = 10;
+/// Where F5 saves the hall of fame, and where it's loaded from at startup.
+const HALL_OF_FAME_PATH: &str = "hall_of_fame.txt";
+
+/// The best genomes of the run, scored by lifetime fitness when their bobble dies.
+#[derive(Resource, Deref, DerefMut)]
+pub struct HallOfFame(pub neat::HallOfFame);
+
+impl Default for HallOfFame {
+    fn default() -> Self {
+        Self(neat::HallOfFame::new(HALL_OF_FAME_SIZE))
+    }
+}
+
+pub fn load_hall_of_fame(mut hall: ResMut<HallOfFame>) {
+    if !Path::new(HALL_OF_FAME_PATH).exists() {
+        return;
+    }
+    match neat::HallOfFame::load(HALL_OF_FAME_PATH, HALL_OF_FAME_SIZE) {
+        Ok(loaded) => {
+            // Genomes saved with a different sensor setup can't drive these brains
+            for genome in loaded.genomes() {
+                let count = |node_type| genome.nodes.values().filter(|&&t| t == node_type).count();
+                if count(NodeType::Input) == BRAIN_INPUTS && count(NodeType::Output) == BRAIN_OUTPUTS {
+                    hall.consider(genome);
+                }
+            }
+            println!("Loaded {} genomes from {}", hall.genomes().len(), HALL_OF_FAME_PATH);
+        }
+        Err(e) => println!("Couldn't load {}: {}", HALL_OF_FAME_PATH, e),
+    }
+}
+
+pub fn save_hall_of_fame(hall: &HallOfFame) {
+    match hall.save(HALL_OF_FAME_PATH) {
+        Ok(()) => println!("Saved {} genomes to {}", hall.genomes().len(), HALL_OF_FAME_PATH),
+        Err(e) => println!("Couldn't save {}: {}", HALL_OF_FAME_PATH, e),
+    }
+}
+
+/// Offers the genome of every bobble about to be despawned. Runs before
+/// `despawn_dead` so the brains are still around.
+pub fn record_hall_of_fame(
+    mut hall: ResMut<HallOfFame>,
+    bobbles: Query<(&Health, &Fitness, &Brain)>,
+) {
+    for (health, fitness, brain) in bobbles.iter() {
+        if health.alive || hall.floor().is_some_and(|floor| fitness.0 as Float <= floor) {
+            continue;
+        }
+        let mut genome = brain.genome.clone();
+        genome.fitness = fitness.0 as Float;
+        hall.consider(&genome);
+    }
+}
+
+/// F5 saves the hall of fame, I spawns a copy of its best genome next to the
+/// target.
+pub fn hall_of_fame_controls(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<SimConfig>,
+    hall: Res<HallOfFame>,
+    target: Single<&Transform, (With<Bobble>, With<Target>)>,
+    mut rng: ResMut<SimRng>,
+) {
+    if kb_input.just_pressed(KeyCode::F5) {
+        save_hall_of_fame(&hall);
+    }
+
+    if kb_input.just_pressed(KeyCode::KeyI) {
+        let Some(best) = hall.best() else {
+            println!("Hall of fame is empty");
+            return;
+        };
+        let rng = &mut rng.0;
+        let mut genome = best.clone();
+        genome.fitness = 0.;
+        spawn_bobble(
+            &mut commands,
+            &asset_server,
+            &config,
+            target.translation.truncate(),
+            Bobble::new(BobbleGender::random(config.male_ratio, rng), 0, BobbleTraits::random(&config, rng)),
+            Brain::new(genome),
+        );
+        println!("Injected hall of famer with fitness {:.1}", best.fitness);
+    }
+}
@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use bevy::app::ScheduleRunnerPlugin;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use neat::{Float, GenerationStats, MetricsCsv};
+
+use crate::brain::Brain;
+use crate::hall_of_fame::{save_hall_of_fame, HallOfFame};
+use crate::infection::Infection;
+use crate::species::BobbleSpecies;
+use crate::{Bobble, Fitness, SIM_STEP};
+
+/// Simulated seconds between stats lines.
+const STATS_INTERVAL: f32 = 60.;
+/// Where stats rows are written unless `BOBBLES_METRICS` names another file.
+const METRICS_PATH: &str = "fitness.csv";
+
+/// True when started with `--headless` or with `BOBBLES_HEADLESS` set.
+pub fn headless_requested() -> bool {
+    std::env::args().any(|arg| arg == "--headless") || std::env::var_os("BOBBLES_HEADLESS").is_some()
+}
+
+/// Everything the simulation needs to run without a window or renderer.
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::ZERO)),
+            AssetPlugin::default(),
+        ))
+        // The scene still creates sprites and meshes, they just never get drawn
+        .init_asset::<Image>()
+        .init_asset::<Mesh>()
+        .init_asset::<ColorMaterial>()
+        // Advance exactly one fixed step per update, as fast as the CPU allows
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(SIM_STEP)))
+        .insert_resource(StatsTimer(Timer::from_seconds(STATS_INTERVAL, TimerMode::Repeating)))
+        .insert_resource(Metrics::open())
+        .add_systems(Update, log_stats);
+    }
+}
+
+#[derive(Resource)]
+struct StatsTimer(Timer);
+
+/// The CSV file stats rows are appended to, if it could be created.
+#[derive(Resource)]
+struct Metrics(Option<MetricsCsv>);
+
+impl Metrics {
+    fn open() -> Self {
+        let path = std::env::var("BOBBLES_METRICS").unwrap_or_else(|_| METRICS_PATH.to_string());
+        match MetricsCsv::create(&path) {
+            Ok(csv) => Self(Some(csv)),
+            Err(e) => {
+                println!("Couldn't create {}: {}", path, e);
+                Self(None)
+            }
+        }
+    }
+}
+
+/// Prints population and fitness stats every `STATS_INTERVAL`, appends them
+/// to the metrics CSV and saves the hall of fame so an interrupted run keeps
+/// its best genomes.
+fn log_stats(
+    time: Res<Time>,
+    mut timer: ResMut<StatsTimer>,
+    mut metrics: ResMut<Metrics>,
+    hall: Res<HallOfFame>,
+    bobbles: Query<(&Bobble, &Fitness), With<Brain>>,
+    infected: Query<(), With<Infection>>,
+    species: Res<BobbleSpecies>,
+) {
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let count = bobbles.iter().count();
+    let best = bobbles.iter().map(|(_, f)| f.0).fold(0., f64::max);
+    let mean = if count > 0 { bobbles.iter().map(|(_, f)| f.0).sum::<f64>() / count as f64 } else { 0. };
+    let generation = bobbles.iter().map(|(b, _)| b.generation).max().unwrap_or(0);
+    println!(
+        "[{:.0}s] Bobbles: {} Infected: {} Highest generation: {} Fitness best {:.1} mean {:.1} Hall of fame best {:.1}",
+        time.elapsed_secs(), count, infected.iter().count(), generation, best, mean,
+        hall.best().map_or(0., |g| g.fitness),
+    );
+
+    if let Some(csv) = metrics.0.as_mut() {
+        let stats = GenerationStats {
+            generation: generation as usize,
+            best_fitness: best as Float,
+            mean_fitness: mean as Float,
+            species_count: species.species.len(),
+            population_size: count,
+            reached_target: false,
+            non_finite: 0,
+        };
+        if let Err(e) = csv.append(&stats) {
+            println!("Couldn't write metrics: {}", e);
+        }
+    }
+
+    save_hall_of_fame(&hall);
+}
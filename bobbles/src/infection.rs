@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::config::SimConfig;
+use crate::sim_rng::SimRng;
+use crate::spatial::SpatialIndex;
+use crate::world::{wrapped_distance, WorldWrap};
+use crate::{Bobble, Health, Target};
+
+/// What infected bobbles are tinted toward, and how far.
+pub const INFECTED_COLOR: Color = Color::srgb(0.5, 0.8, 0.1);
+pub const INFECTED_TINT: f32 = 0.6;
+
+/// Carried by a sick bobble until it recovers, or dies of it first.
+#[derive(Component)]
+pub struct Infection {
+    recovery: Timer,
+}
+
+impl Infection {
+    pub fn new(config: &SimConfig) -> Self {
+        Self {
+            recovery: Timer::from_seconds(config.infection_recovery_time, TimerMode::Once),
+        }
+    }
+}
+
+/// Carried by a bobble that got over an infection. It can't catch it again.
+#[derive(Component)]
+pub struct Immune;
+
+/// Gives healthy bobbles near infected ones a chance of catching it, which
+/// grows with how many infected are within `infection_radius`.
+pub fn spread_infection(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<SimConfig>,
+    wrap: Res<WorldWrap>,
+    index: Res<SpatialIndex>,
+    infected: Query<(), With<Infection>>,
+    susceptible: Query<(Entity, &Transform), (With<Bobble>, Without<Infection>, Without<Immune>, Without<Target>)>,
+    mut rng: ResMut<SimRng>,
+) {
+    if infected.is_empty() {
+        return;
+    }
+    let rng = &mut rng.0;
+    let step_chance = (config.transmission_rate * time.delta_secs_f64()).clamp(0., 1.);
+
+    for (entity, transform) in &susceptible {
+        let position = transform.translation.truncate();
+        let exposures = index.bobbles.near(position, config.infection_radius, wrap.0)
+            .filter(|(carrier, at)| infected.contains(*carrier) && wrapped_distance(position, *at, wrap.0) < config.infection_radius)
+            .count();
+        if exposures == 0 {
+            continue;
+        }
+        // Every carrier close by is another chance to pass it on
+        let chance = 1. - (1. - step_chance).powi(exposures as i32);
+        if rng.random_bool(chance) {
+            commands.entity(entity).insert(Infection::new(&config));
+        }
+    }
+}
+
+/// Drains the health of infected bobbles, which can't heal meanwhile, and
+/// makes the ones that last until recovery immune.
+pub fn progress_infections(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<SimConfig>,
+    mut infected: Query<(Entity, &mut Infection, &mut Health)>,
+) {
+    for (entity, mut infection, mut health) in infected.iter_mut() {
+        health.health -= config.infection_damage * time.delta_secs();
+        health.timer.reset();
+
+        if infection.recovery.tick(time.delta()).just_finished() {
+            commands.entity(entity).remove::<Infection>().insert(Immune);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use bevy::prelude::*;
+
+use crate::brain::Brain;
+use crate::infection::{Immune, Infection};
+use crate::species::SpeciesColor;
+use crate::thirst::Thirst;
+use crate::{Bobble, Energy, Fitness, Health, Hunger, LifetimeStats, SelectedBobble, SIM_STEP};
+
+/// Marks the text panel showing the selected bobble's state.
+#[derive(Component)]
+pub struct Inspector;
+
+/// The panel sits on the right, under the network view.
+pub fn setup_inspector(mut commands: Commands) {
+    commands.spawn((
+        Inspector,
+        Text::new(""),
+        TextFont {
+            font_size: 14.,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(224),
+            right: px(12),
+            ..default()
+        },
+    ));
+}
+
+/// Rewrites the panel for the selected bobble every frame, or blanks it once
+/// there's nothing (left) to show.
+pub fn update_inspector(
+    selected: Res<SelectedBobble>,
+    mut panel: Single<&mut Text, With<Inspector>>,
+    bobbles: Query<(&Bobble, &Hunger, &Health, &Energy, Option<&Thirst>, Option<&Fitness>, Option<&LifetimeStats>, Option<&Brain>, Option<&SpeciesColor>, Has<Infection>, Has<Immune>)>,
+) {
+    let Some((bobble, hunger, health, energy, thirst, fitness, stats, brain, species, infected, immune)) =
+        selected.0.and_then(|entity| bobbles.get(entity).ok())
+    else {
+        if !panel.is_empty() {
+            **panel = Text::new("");
+        }
+        return;
+    };
+
+    let mut lines = vec![
+        format!("{:?} {:?}, age {:.0}s, generation {}", bobble.gender, bobble.traits.diet, bobble.age, bobble.generation),
+        format!("Size {:.2}", bobble.traits.size),
+        format!("Hunger {:.1} / {:.1}", hunger.hunger, hunger.max_hunger),
+        format!("Health {:.1} / {:.1}", health.health, health.max_health),
+        format!("Energy {:.1} / {:.1}{}", energy.energy, energy.max_energy, if energy.resting { " (resting)" } else { "" }),
+    ];
+    if let Some(thirst) = thirst {
+        lines.push(format!("Thirst {:.1} / {:.1}", thirst.thirst, thirst.max_thirst));
+    }
+    if infected {
+        lines.push("Infected".to_string());
+    } else if immune {
+        lines.push("Immune".to_string());
+    }
+    if let Some(fitness) = fitness {
+        lines.push(format!("Fitness {:.1}", fitness.0));
+    }
+    if let Some(stats) = stats {
+        lines.push(format!(
+            "Ate {:.0}, walked {:.0} over {} cells, {} children, alive {:.0}s",
+            stats.food_eaten,
+            stats.distance_traveled,
+            stats.visited_cells.len(),
+            stats.offspring_count,
+            stats.ticks_alive as f64 * SIM_STEP,
+        ));
+    }
+    if let Some(brain) = brain {
+        let (nodes, connections) = brain.genome.complexity();
+        lines.push(format!("Brain: {} nodes, {} connections", nodes, connections));
+    }
+    if let Some(species) = species {
+        lines.push(format!("Species #{}", species.id));
+    }
+    **panel = Text::new(lines.join("\n"));
+}
@@ -1,247 +1,215 @@
+// Bevy systems take their data as arguments and queries as tuples
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+use std::collections::HashSet;
+use std::f32::consts::FRAC_PI_2;
+
 use bevy::{
     post_process::bloom::Bloom, prelude::*
 };
+use neat::{Float, MutationRates};
 use rand::Rng;
-use rand::prelude::*;
-use std::collections::{HashMap, HashSet};
-
-// Neural Network Stuffs
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum NodeType {
-    Input,
-    Hidden,
-    Output,
-}
-
-#[derive(Debug, Clone)]
-struct Connection {
-    from_idx: usize,
-    to_idx: usize,
-    weight: f32,
-    enabled: bool,
-    innovation: usize,
-}
 
-impl Connection {
-    fn new(from_idx: usize, to_idx: usize, weight: f32, innovation: usize) -> Self {
-        Self {
-            from_idx,
-            to_idx,
-            weight,
-            enabled: true,
-            innovation,
-        }
-    }
-}
-
-#[derive(Debug, Clone, Default)]
-struct Genome {
-    nodes: HashMap<usize, NodeType>,
-    connections: Vec<Connection>,
-    pub fitness: f32,
-}
-
-
-#[derive(Resource, Default)]
-struct InnovationTracker {
-    current_number: usize,
-    history: HashMap<(usize, usize), usize>, // (from, to) -> innovation_id
-}
-
-#[derive(Component)]
-struct NeuralNetwork {
-    nodes: Vec<NodeState>,
-    execution_order: Vec<usize>,
-    inputs_count: usize,
-    output_indices: Vec<usize>,
-}
-
-pub struct NodeState {
-    pub id: usize,
-    pub value: f32,
-    pub incoming: Vec<(usize, f32)>, // (index_in_nodes_vec, weight)
-    pub node_type: NodeType,
-}
+mod ancestry;
+mod brain;
+mod coloring;
+mod config;
+mod corpse;
+mod daynight;
+mod events;
+mod extinction;
+mod food;
+mod foraging;
+mod hall_of_fame;
+mod headless;
+mod infection;
+mod inspector;
+mod minimap;
+mod network_view;
+mod obstacle;
+mod pheromone;
+mod pulse;
+mod reproduction;
+mod sandbox;
+mod sim_rng;
+mod spatial;
+mod species;
+mod status_bars;
+mod thirst;
+mod trail;
+mod vision;
+mod world;
+
+use ancestry::{flush_ancestry, tag_founders, Ancestry};
+use brain::{think, update_fitness, Brain, Innovations, Momentum};
+use coloring::{cycle_color_mode, update_bobble_colors, BaseColor, ColorMode, Highlighted, KinHue};
+use config::{reload_config, ConfigWatch, SimConfig};
+use corpse::{animate_dying, decay_corpses, spawn_corpse, spawn_dying, Corpse};
+use daynight::{advance_game_time, tint_world, GameTime};
+use events::{setup_event_feed, stamp_events, update_event_feed, EventFeed, SimEvents};
+use extinction::{record_fallen, reseed_on_extinction, Extinctions, ReseedSource};
+use foraging::fittest_random_brain;
+use food::{plant_color, plant_nutrition, Diet, FoodType, Poisonous};
+use hall_of_fame::{hall_of_fame_controls, load_hall_of_fame, record_hall_of_fame, HallOfFame};
+use headless::{headless_requested, HeadlessPlugin};
+use infection::{progress_infections, spread_infection, Immune, Infection};
+use inspector::{setup_inspector, update_inspector, Inspector};
+use minimap::{draw_minimap, toggle_minimap, Minimap};
+use network_view::{draw_network_view, toggle_network_view, NetworkView};
+use obstacle::{push_out_of_obstacles, spawn_obstacles};
+use pheromone::{deposit_pheromones, spread_pheromones, Pheromones};
+use pulse::{pulse_bloom, toggle_bloom_pulse, BloomPulse};
+use reproduction::{
+    asexual_reproduction, bobble_reproducing_collision, bud_offspring, seek_mates, sexual_reproduction,
+    update_mating_readiness, update_pregnancies, update_reproduction_cooldowns, Pregnant,
+};
+use sandbox::sandbox_controls;
+use sim_rng::SimRng;
+use spatial::{rebuild_spatial_index, SpatialGrid, SpatialIndex};
+use species::{assign_species, setup_species_legend, update_species_legend, BobbleSpecies, SpeciesLegend};
+use status_bars::{level_status_bars, status_bars, toggle_status_bars, update_status_bars, StatusBarsVisible};
+use thirst::{bobble_drinking_collision, spawn_water, update_thirst, Thirst, THIRST_ENABLED};
+use trail::{draw_trails, record_trails, toggle_trails, Trails};
+use vision::Heading;
+use world::{enforce_world_edges, wrap_world, wrapped_distance, wrapped_offset, SpawnLayout, WorldEdge, WorldWrap};
 
+// Neural Network Stuffs
+/// A brain-driven bobble's `LifetimeStats` weighed into one score.
 #[derive(Component)]
 struct Fitness(f64);
 
-#[derive(Resource, Default)]
-pub struct InnovationHistory {
-    pub map: HashMap<(usize, usize), usize>,
-    pub next_innovation: usize,
-    pub next_node_id: usize,
+/// What a bobble has done with its life so far. Newborns start from nothing.
+#[derive(Component, Default)]
+struct LifetimeStats {
+    food_eaten: f32,
+    distance_traveled: f32,
+    offspring_count: u32,
+    ticks_alive: u32,
+    /// Grid cells it has been in, as (column, row). The world is finite, so
+    /// this can't grow past its number of cells.
+    visited_cells: HashSet<(i32, i32)>,
 }
 
-impl InnovationHistory {
-    pub fn get_innovation(&mut self, from: usize, to: usize) -> usize {
-        if let Some(&id) = self.map.get(&(from, to)) {
-            id
-        } else {
-            let id = self.next_innovation;
-            self.map.insert((from, to), id);
-            self.next_innovation += 1;
-            id
-        }
-    }
-}
-
-impl Genome {
-    pub fn compile(&self) -> NeuralNetwork {
-        let mut nodes_vec = Vec::new();
-        let mut id_to_idx = HashMap::new();
-        
-        for (id, node_type) in &self.nodes {
-            id_to_idx.insert(*id, nodes_vec.len());
-            nodes_vec.push(NodeState {
-                id: *id,
-                value: 0.0,
-                incoming: Vec::new(),
-                node_type: *node_type,
-            });
-        }
-
-        for conn in self.connections.iter().filter(|c| c.enabled) {
-            let to_idx = id_to_idx[&conn.to_idx];
-            let from_idx = id_to_idx[&conn.from_idx];
-            nodes_vec[to_idx].incoming.push((from_idx, conn.weight));
-        }
-
-        let mut execution_order = Vec::new();
-        let mut visited = HashSet::new();
-        
-        fn visit(
-            idx: usize, 
-            nodes: &Vec<NodeState>, 
-            visited: &mut HashSet<usize>, 
-            order: &mut Vec<usize>,
-            id_to_idx: &HashMap<usize, usize>
-        ) {
-            if visited.contains(&idx) || nodes[idx].node_type == NodeType::Input { return; }
-            for (from_idx, _) in &nodes[idx].incoming {
-                visit(*from_idx, nodes, visited, order, id_to_idx);
-            }
-            visited.insert(idx);
-            order.push(idx);
-        }
-
-        let output_indices: Vec<usize> = nodes_vec.iter().enumerate()
-            .filter(|(_, n)| n.node_type == NodeType::Output)
-            .map(|(i, _)| i).collect();
-
-        for &out_idx in &output_indices {
-            visit(out_idx, &nodes_vec, &mut visited, &mut execution_order, &id_to_idx);
-        }
-
-        NeuralNetwork {
-            nodes: nodes_vec,
-            execution_order,
-            inputs_count: self.nodes.values().filter(|&&t| t == NodeType::Input).count(),
-            output_indices,
-        }
+impl LifetimeStats {
+    /// The stats weighed by the config's fitness weights.
+    fn score(&self, config: &SimConfig) -> f64 {
+        config.fitness_food * self.food_eaten as f64
+            + config.fitness_distance * self.distance_traveled as f64
+            + config.fitness_offspring * self.offspring_count as f64
+            + config.fitness_survival * self.ticks_alive as f64 * SIM_STEP
+            + config.fitness_exploration * self.visited_cells.len() as f64
     }
 }
 
-impl NeuralNetwork {
-    pub fn activate(&mut self, inputs: &[f32]) -> Vec<f32> {
-        let mut input_ptr = 0;
-        for node in &mut self.nodes {
-            if node.node_type == NodeType::Input {
-                node.value = inputs[input_ptr];
-                input_ptr += 1;
-            }
-        }
-
-        for &idx in &self.execution_order {
-            let sum: f32 = self.nodes[idx].incoming.iter()
-                .map(|(from_idx, weight)| self.nodes[*from_idx].value * weight)
-                .sum();
-            self.nodes[idx].value = sum.tanh(); // Using Tanh for -1 to 1 output
-        }
-
-        self.output_indices.iter().map(|&i| self.nodes[i].value).collect()
-    }
-}
-
-// --- 5. MUTATION LOGIC ---
-
-impl Genome {
-    pub fn mutate(&mut self, history: &mut InnovationHistory) {
-        let mut rng = rand::rng();
-        let mutation_type: f32 = rng.random();
-
-        if mutation_type < 0.8 { // 80% Weight Mutation
-            for conn in &mut self.connections {
-                if rng.random_bool(0.9) {
-                    conn.weight += rng.random_range(-0.1..0.1); // Nudge
-                } else {
-                    conn.weight = rng.random_range(-1.0..1.0); // Reset
-                }
-            }
-        } else if mutation_type < 0.85 { // 5% Add Connection
-            let keys: Vec<usize> = self.nodes.keys().cloned().collect();
-            let from_idx = *keys.choose(&mut rng).unwrap();
-            let to_idx = *keys.choose(&mut rng).unwrap();
-            
-            // Basic check: don't connect to an input, and don't connect to self
-            if self.nodes[&to_idx] != NodeType::Input && from_idx != to_idx {
-                let innov = history.get_innovation(from_idx, to_idx);
-                self.connections.push(Connection {
-                    from_idx, to_idx, weight: rng.random_range(-1.0..1.0), enabled: true, innovation: innov,
-                });
-            }
-        } else if mutation_type < 0.88 { // 3% Add Node
-            if let Some(conn) = self.connections.iter_mut().filter(|c| c.enabled).choose(&mut rng) {
-                conn.enabled = false;
-                let new_id = history.next_node_id;
-                history.next_node_id += 1;
-                
-                self.nodes.insert(new_id, NodeType::Hidden);
-                
-                // Add two connections to replace the old one
-                let innov1 = history.get_innovation(conn.from_idx, new_id);
-                let innov2 = history.get_innovation(new_id, conn.to_idx);
-                
-                //self.connections.push(Connection { from_idx: conn.from_idx, to_idx: new_id, weight: 1.0, enabled: true, innovation: innov1 });
-                //self.connections.push(Connection { from_idx: new_id, to_idx: conn.to_idx, weight: conn.weight, enabled: true, innovation: innov2 });
-            }
-        }
-    }
-}
+/// Largest magnitude a connection weight may reach before being clamped.
+const WEIGHT_CAP: Float = 8.;
+/// Let add-connection mutations create recurrent (backward/looping) edges.
+/// Off keeps every brain strictly feed-forward.
+const RECURRENT_CONNECTIONS: bool = false;
+/// Chance of each kind of mutation when a brain is copied into a child or a
+/// reseeded bobble.
+const MUTATION_RATES: MutationRates = MutationRates {
+    weight: 0.8,
+    add_connection: 0.05,
+    add_node: 0.03,
+    delete_connection: 0.,
+    toggle_enable: 0.,
+    activation: 0.,
+};
 
 // Simulation Stuffs
 
-/// Target movement speed factor.
-const TARGET_SPEED: f32 = 200.;
+/// Seconds of simulated time per fixed update. Everything that changes the
+/// world runs on this step so behavior doesn't depend on frame rate.
+const SIM_STEP: f64 = 1. / 60.;
+
+/// Side length of the square world, centered on the origin.
+const WORLD_SIZE: f32 = 1000.;
+/// Color of the world plane. Matches what the old negative blue channel clamped to.
+const WORLD_COLOR: Color = Color::srgb(0.2, 0.2, 0.);
+/// Depth of the world plane; everything else sits at z >= 0 so it draws on top.
+const WORLD_Z: f32 = -1.;
 /// How quickly should the camera snap to the desired location.
 const CAMERA_DECAY_RATE: f32 = 5.;
+/// How quickly a bobble turns to face the way it's moving.
+const TURN_DECAY_RATE: f32 = 8.;
+/// Below this speed a bobble keeps facing the way it was, so standing still
+/// doesn't make it spin on noise.
+const MIN_TURN_SPEED: f32 = 5.;
+/// How much of each step's measured velocity goes into the smoothed one.
+const VELOCITY_SMOOTHING: f32 = 0.2;
+/// Jumps further than this in one step are teleports (respawns, being
+/// injected), not movement, and leave the velocity alone.
+const TELEPORT_DISTANCE: f32 = 50.;
+/// A bobble starts resting once slower than `REST_SPEED` and only counts as
+/// moving again past `MOVE_SPEED`, so drifting around one speed doesn't
+/// keep flipping it between the two.
+const REST_SPEED: f32 = 2.;
+const MOVE_SPEED: f32 = 5.;
 const PLAYER_SCALE: f32 = 64.;
 const PLANT_SCALE: f32 = 32.;
-const HUNGER_RATE: f32 = 1.0; // How much hunger decreases every gamestep
-const HEALING_RATE: f32 = 0.2; 
-const ENERGY_RATE: f32 = 0.2; 
-const INITIAL_SPAWN: i32 = 20;
-const COLLISION_DISTANCE: f32 = 8.;
-const START_HEALING_TIME: f32 = 10.;
-const START_RESTING_TIME: f32 = 2.;
-
-const PREGNANCY_TIME: f32 = 20.;
+/// Body sizes a bobble can be born with, and how far a child's can stray
+/// from its parents'.
+const BODY_SIZE_RANGE: std::ops::RangeInclusive<f32> = 0.7..=1.3;
+const BODY_SIZE_DRIFT: f32 = 0.05;
+/// How far a child's inherited sex bias can stray from its parents'.
+const SEX_BIAS_DRIFT: f64 = 0.02;
+// Rates, speeds, timers and spawn counts live in SimConfig
 
 const MALE_COLOR: Color = Color::srgb(0., 0., 1.);
 const FEMALE_COLOR: Color = Color::srgb(1., 0., 1.);
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum BobbleGender {
     Male,
     Female
 }
 
+impl BobbleGender {
+    /// Male with chance `male_chance`, which is clamped to 0 to 1.
+    fn random(male_chance: f64, rng: &mut impl Rng) -> Self {
+        if rng.random_bool(male_chance.clamp(0., 1.)) {
+            BobbleGender::Male
+        } else {
+            BobbleGender::Female
+        }
+    }
+}
+
+fn color_for_gender(gender: &BobbleGender) -> Color {
+    match gender {
+        BobbleGender::Male => MALE_COLOR,
+        BobbleGender::Female => FEMALE_COLOR,
+    }
+}
+
 #[derive(Component)]
 struct Target;
 
 #[derive(Component)]
 struct Bobble {
-    age: i32,
+    age: f32, // Seconds of game time since it appeared
     gender: BobbleGender,
+    generation: u32, // 0 for seeded bobbles, one more than the older parent otherwise
+    /// What it was born with and passes on to its children.
+    traits: BobbleTraits,
+}
+
+impl Bobble {
+    fn new(gender: BobbleGender, generation: u32, traits: BobbleTraits) -> Self {
+        Self {
+            age: 0.,
+            gender,
+            generation,
+            traits,
+        }
+    }
+
+    /// `base` adjusted for body size: `coefficient` is how much it changes,
+    /// as a fraction, per unit of size above 1.
+    fn size_scaled(&self, base: f32, coefficient: f32) -> f32 {
+        base * (1. + coefficient * (self.traits.size - 1.)).max(0.)
+    }
 }
 
 #[derive(Component)]
@@ -262,15 +230,26 @@ struct Health {
 struct Energy {
     energy: f32,
     max_energy: f32,
-    timer: Timer,
+    timer: Timer, // Time spent resting, energy comes back once it finishes
+    resting: bool,
 }
 
 #[derive(Component)]
 struct Plant;
 
+/// The ground everything stands on.
+#[derive(Component)]
+struct WorldPlane;
+
 #[derive(Component)]
 struct Collider;
 
+/// How far from its centre a sprite can be touched, which grows with the
+/// sprite.
+fn collision_radius(sprite: &Sprite, config: &SimConfig) -> f32 {
+    sprite.custom_size.map_or(0., |size| size.max_element() / 2.) * config.collision_scale
+}
+
 #[derive(Component)]
 struct Edible {
     nutrition_value: f32,
@@ -288,18 +267,98 @@ struct Reproducing {
     
 }
 
+/// The bobble last clicked on, if any.
+#[derive(Resource, Default)]
+struct SelectedBobble(Option<Entity>);
+
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins)
-        .add_plugins(MeshPickingPlugin) 
-        .add_systems(Startup, (setup_scene, setup_camera, setup_ui))
-        .add_systems(Update, (
-            ((update_health, update_hunger, update_energy), despawn_dead).chain(),
-            (move_target, update_camera).chain(),
-            bobble_eating_collision,
-            update_ui,
-            update_velocity,
+    let mut app = App::new();
+
+    // Rendering and input only exist with a window; headless runs just the sim
+    if headless_requested() {
+        app.add_plugins(HeadlessPlugin);
+    } else {
+        app.add_plugins(DefaultPlugins)
+            .add_plugins(MeshPickingPlugin) 
+            .init_resource::<NetworkView>()
+            .init_resource::<Minimap>()
+            .init_resource::<StatusBarsVisible>()
+            .init_resource::<ColorMode>()
+            .init_resource::<Trails>()
+            .init_resource::<BloomPulse>()
+            .add_systems(Startup, (setup_camera, setup_ui, setup_event_feed, setup_inspector, setup_species_legend))
+            .add_systems(FixedUpdate, move_target)
+            .add_systems(Update, (
+                update_camera,
+                update_ui,
+                update_event_feed,
+                update_inspector,
+                (toggle_network_view, draw_network_view).chain(),
+                (toggle_minimap, draw_minimap).chain(),
+                face_movement,
+                tint_world,
+                (toggle_status_bars, update_status_bars, level_status_bars.after(face_movement)),
+                (cycle_color_mode, update_bobble_colors, update_species_legend).chain(),
+                (toggle_trails, record_trails, draw_trails).chain(),
+                (toggle_bloom_pulse, pulse_bloom).chain(),
+                hall_of_fame_controls,
+                sandbox_controls,
+            ));
+    }
+
+    if THIRST_ENABLED {
+        app.add_systems(Startup, spawn_water.after(spawn_obstacles))
+            .add_systems(FixedUpdate, (bobble_drinking_collision, update_thirst).chain().before(update_health));
+    }
+
+    app.insert_resource(Time::<Fixed>::from_seconds(SIM_STEP))
+        .insert_resource(SimConfig::load())
+        .init_resource::<ConfigWatch>()
+        .init_resource::<Innovations>()
+        .init_resource::<SelectedBobble>()
+        .init_resource::<WorldEdge>()
+        .init_resource::<WorldWrap>()
+        .init_resource::<SimRng>()
+        .init_resource::<SpawnLayout>()
+        .init_resource::<ReseedSource>()
+        .init_resource::<Extinctions>()
+        .init_resource::<HallOfFame>()
+        .init_resource::<SimEvents>()
+        .init_resource::<GameTime>()
+        .init_resource::<Pheromones>()
+        .init_resource::<BobbleSpecies>()
+        .init_resource::<Ancestry>()
+        .init_resource::<SpatialIndex>()
+        // Everything drawing from SimRng runs in a fixed order, so a seed
+        // replays the same way
+        .add_systems(Startup, ((setup_scene, spawn_obstacles).chain(), load_hall_of_fame))
+        .add_systems(FixedUpdate, (
+            advance_game_time.before(think).before(update_hunger),
+            rebuild_spatial_index.before(think).before(spread_infection).before(seek_mates),
+            age_bobbles.before(update_mating_readiness),
+            ((update_health, update_hunger, update_energy, update_fitness), (record_fallen, record_hall_of_fame), despawn_dead, reseed_on_extinction.after(bud_offspring)).chain(),
+            bobble_eating_collision.before(spread_infection),
+            decay_corpses,
+            animate_dying,
+            (spread_infection, progress_infections).chain().before(update_health),
+            (
+                update_reproduction_cooldowns,
+                update_mating_readiness,
+                (seek_mates, bobble_reproducing_collision).run_if(sexual_reproduction),
+                // Pregnancies already under way still finish after a switch
+                update_pregnancies,
+                bud_offspring.run_if(asexual_reproduction),
+            ).chain().after(spread_infection),
+            (tag_founders.before(update_reproduction_cooldowns), flush_ancestry.after(bud_offspring)),
+            update_velocity.after(wrap_world),
+            (deposit_pheromones, spread_pheromones).chain().before(think),
+            assign_species,
+            think,
+            push_out_of_obstacles.after(think).after(move_target).after(seek_mates),
+            (enforce_world_edges, wrap_world).after(push_out_of_obstacles),
         ))
+        .add_systems(PreUpdate, stamp_events)
+        .add_systems(Update, reload_config)
         .run();
 }
 
@@ -308,24 +367,27 @@ fn setup_scene(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     asset_server: Res<AssetServer>,
+    config: Res<SimConfig>,
+    layout: Res<SpawnLayout>,
+    mut innovations: ResMut<Innovations>,
+    mut rng: ResMut<SimRng>,
 ) {
     // World where we move the target
     commands.spawn((
-        Mesh2d(meshes.add(Rectangle::new(1000., 1000.))),
-        MeshMaterial2d(materials.add(Color::srgb(0.2, 0.2, -1.))),
+        WorldPlane,
+        Mesh2d(meshes.add(Rectangle::new(WORLD_SIZE, WORLD_SIZE))),
+        MeshMaterial2d(materials.add(WORLD_COLOR)),
+        Transform::from_xyz(0., 0., WORLD_Z),
     ));
 
     // Target
     commands.spawn((
         Target,
         Transform::from_xyz(0., 0., 0.),
-        Bobble {
-            age: 10,
-            gender: BobbleGender::Male,
-        },
+        Bobble::new(BobbleGender::Male, 0, BobbleTraits::default()),
         Sprite {
             image: asset_server.load("human.png"),
-            color: Color::srgb(1., 0., 1.),
+            color: color_for_gender(&BobbleGender::Male),
             custom_size: Some(Vec2::new(PLAYER_SCALE, PLAYER_SCALE)),
             ..default()
         },
@@ -341,12 +403,13 @@ fn setup_scene(
             health: 100.,
             max_health: 100.,
             alive: true,
-            timer: Timer::from_seconds(START_HEALING_TIME, TimerMode::Once),
+            timer: Timer::from_seconds(config.start_healing_time, TimerMode::Once),
         },
         Energy {
             energy: 100.,
             max_energy: 100.,
-            timer: Timer::from_seconds(START_RESTING_TIME, TimerMode::Once),
+            timer: Timer::from_seconds(config.start_resting_time, TimerMode::Once),
+            resting: true,
         },
         Movement {
             velocity: Vec2::new(0.0, 0.0),
@@ -354,105 +417,227 @@ fn setup_scene(
             last_y: 0.0,
         },
         Collider,
+        status_bars(),
+        BaseColor(color_for_gender(&BobbleGender::Male)),
     ));
 
-    let mut rng = rand::rng();
-    for _ in 1..=INITIAL_SPAWN {
-        let x: f32 = rng.random_range(-500_f32..=500_f32);
-        let y: f32 = rng.random_range(-500_f32..=500_f32);
+    let rng = &mut rng.0;
+    // Keep everything apart to start with, the target included
+    let mut placed = SpatialGrid::new(config.spawn_spacing);
+    placed.insert((), Vec2::ZERO);
+    for i in 0..config.initial_spawn {
+        let bobble = spawn_bobble(
+            &mut commands,
+            &asset_server,
+            &config,
+            layout.bobbles.sample_spaced(&mut placed, config.spawn_spacing, rng),
+            Bobble::new(BobbleGender::random(config.male_ratio, rng), 0, BobbleTraits::random(&config, rng)),
+            fittest_random_brain(config.foraging_candidates, &mut innovations, &config, rng),
+        );
+        // They're placed at random anyway, so the first few will do
+        if i < config.initial_infections {
+            commands.entity(bobble).insert(Infection::new(&config));
+        }
+    }
 
-        let max_hunger: f32 = rng.random_range(50.0..=200.0);
-        let max_health: f32 = rng.random_range(50.0..=200.0);
-        let max_energy: f32 = rng.random_range(100.0..=150.0);
-        let hover_color: Color = Color::srgb(6.25, 9.4, 9.1);
+    //Plant
+    for _ in 0..config.plant_count {
+        let position = layout.plants.sample_spaced(&mut placed, config.spawn_spacing, rng);
+        spawn_plant(&mut commands, &asset_server, &config, position, rng);
+    }
+}
 
-        let is_male: bool = rng.random_bool(0.5);
-        let start_color: Color = if is_male {
-            MALE_COLOR
-        } else {
-            FEMALE_COLOR
-        };
+/// A plant of a random kind at `position`, poisonous by chance.
+fn spawn_plant(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    config: &SimConfig,
+    position: Vec2,
+    rng: &mut impl Rng,
+) -> Entity {
+    let food_type = FoodType::random(rng);
+    let poison = Poisonous::roll(rng);
+    let poisonous = poison.is_some();
+
+    let mut plant = commands.spawn((
+        Plant,
+        food_type,
+        Health {
+            health: 100.,
+            max_health: 100.,
+            alive: true,
+            timer: Timer::from_seconds(config.start_healing_time, TimerMode::Once),
+        },
+        Sprite {
+            image: asset_server.load("plant.png"),
+            color: plant_color(food_type, poisonous),
+            custom_size: Some(Vec2::new(PLANT_SCALE, PLANT_SCALE)),
+            ..default()
+        },
+        Edible {
+            nutrition_value: plant_nutrition(food_type, poisonous, rng),
+        },
+        Collider,
+        Transform::from_translation(position.extend(0.)),
+    ));
+    if let Some(poison) = poison {
+        plant.insert(poison);
+    }
+    plant.id()
+}
 
-        //Bobble
-        commands.spawn((
-            Bobble {
-                age: 10,
-                gender: if is_male { 
-                    BobbleGender::Male
-                } else {
-                    BobbleGender::Female
-                },
-            },
-            Hunger {
-                hunger: max_hunger,
-                max_hunger: max_hunger,
-            },
-            Health {
-                health: max_health,
-                max_health: max_health,
-                alive: true,
-                timer: Timer::from_seconds(START_HEALING_TIME, TimerMode::Once),
-            },
-            Sprite {
-                image: asset_server.load("human.png"),
-                color: start_color,
-                custom_size: Some(Vec2::new(PLAYER_SCALE, PLAYER_SCALE)),
-                ..default()
-            },
-            Transform::from_xyz(x, y, 0.),
-            Pickable {
-                should_block_lower: true,
-                is_hoverable: true, 
-            },
-        ))
-        .observe(|trigger: On<Pointer<Click>>, query: Query<(&Hunger, &Health)>| {
-            println!("Click");
-            let clicked_entity = trigger.entity;
+/// Maximum stats a bobble is born with, its kin hue, diet and body size.
+#[derive(Debug, Clone, Copy)]
+struct BobbleTraits {
+    max_hunger: f32,
+    max_health: f32,
+    max_energy: f32,
+    hue: KinHue,
+    diet: Diet,
+    /// Sprite scale relative to `PLAYER_SCALE`. Bigger bobbles are tougher
+    /// but slower and hungrier, by the config's `size_*` coefficients.
+    size: f32,
+    /// Added to the male chance of this bobble's children under
+    /// `SexDetermination::Inherited`.
+    sex_bias: f64,
+}
 
-            if let Ok((hunger, health)) = query.get(clicked_entity) {
-                println!("Hunger: {}, Health: {}", hunger.hunger, health.health);
-            }
-        })
-        .observe(move |trigger: On<Pointer<Over>>, mut query: Query<&mut Sprite>| {
-            if let Ok(mut sprite_handle) = query.get_mut(trigger.entity) {
-                sprite_handle.color = hover_color;
-            }
-        })
-        .observe(move |trigger: On<Pointer<Out>>, mut query: Query<&mut Sprite>| {
-            if let Ok(mut sprite_handle) = query.get_mut(trigger.entity) {
-                sprite_handle.color = start_color;
-            }
-        });
+impl BobbleTraits {
+    /// A founder's traits, with stats from the config's ranges.
+    fn random(config: &SimConfig, rng: &mut impl Rng) -> Self {
+        let mut draw = |(min, max): (f32, f32)| rng.random_range(min..=max);
+        Self {
+            max_hunger: draw(config.max_hunger_range),
+            max_health: draw(config.max_health_range),
+            max_energy: draw(config.max_energy_range),
+            hue: KinHue::random(rng),
+            diet: Diet::random(rng),
+            size: rng.random_range(BODY_SIZE_RANGE),
+            sex_bias: 0.,
+        }
     }
 
-    //Plant
-    for _ in 1..=INITIAL_SPAWN {
-        let x: f32 = rng.random_range(-500_f32..=500_f32);
-        let y: f32 = rng.random_range(-500_f32..=500_f32);
-
-        commands.spawn((
-            Plant,
-            Health {
-                health: 100.,
-                max_health: 100.,
-                alive: true,
-                timer: Timer::from_seconds(START_HEALING_TIME, TimerMode::Once),
-            },
-            Sprite {
-                image: asset_server.load("plant.png"),
-                color: Color::srgb(0., 1., 0.),
-                custom_size: Some(Vec2::new(PLANT_SCALE, PLANT_SCALE)),
-                ..default()
-            },
-            Edible {
-                nutrition_value: 100.,
-            },
-            Collider,
-            Transform::from_xyz(x, y, 0.),
-        ));
+    /// Halfway between two parents, with the diet of one or the other.
+    fn blend(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            max_hunger: (self.max_hunger + other.max_hunger) / 2.,
+            max_health: (self.max_health + other.max_health) / 2.,
+            max_energy: (self.max_energy + other.max_energy) / 2.,
+            hue: self.hue.blend(other.hue),
+            diet: if rng.random_bool(0.5) { self.diet } else { other.diet },
+            size: (self.size + other.size) / 2.,
+            sex_bias: (self.sex_bias + other.sex_bias) / 2.,
+        }
+    }
+
+    /// Nudges the hue, size and sex bias a little, so children aren't exact
+    /// blends. Stats are kept within the config's ranges, which may have
+    /// changed since the parents were born.
+    fn mutate(mut self, config: &SimConfig, rng: &mut impl Rng) -> Self {
+        let within = |value: f32, (min, max): (f32, f32)| value.clamp(min, max);
+        self.max_hunger = within(self.max_hunger, config.max_hunger_range);
+        self.max_health = within(self.max_health, config.max_health_range);
+        self.max_energy = within(self.max_energy, config.max_energy_range);
+        self.hue = self.hue.drift(rng);
+        self.size = (self.size + rng.random_range(-BODY_SIZE_DRIFT..=BODY_SIZE_DRIFT))
+            .clamp(*BODY_SIZE_RANGE.start(), *BODY_SIZE_RANGE.end());
+        self.sex_bias = (self.sex_bias + rng.random_range(-SEX_BIAS_DRIFT..=SEX_BIAS_DRIFT)).clamp(-1., 1.);
+        self
     }
 }
 
+impl Default for BobbleTraits {
+    /// Middling stats, for the target.
+    fn default() -> Self {
+        Self {
+            max_hunger: 100.,
+            max_health: 100.,
+            max_energy: 100.,
+            hue: KinHue(0.),
+            diet: Diet::Omnivore,
+            size: 1.,
+            sex_bias: 0.,
+        }
+    }
+}
+
+/// Spawns a brain-driven bobble with full stats, selectable by clicking.
+fn spawn_bobble(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    config: &SimConfig,
+    position: Vec2,
+    bobble: Bobble,
+    brain: Brain,
+) -> Entity {
+    let hover_color: Color = Color::srgb(6.25, 9.4, 9.1);
+    let start_color = color_for_gender(&bobble.gender);
+    let traits = bobble.traits;
+    let max_health = bobble.size_scaled(traits.max_health, config.size_health);
+
+    commands.spawn((
+        bobble,
+        Hunger {
+            hunger: traits.max_hunger,
+            max_hunger: traits.max_hunger,
+        },
+        Health {
+            health: max_health,
+            max_health,
+            alive: true,
+            timer: Timer::from_seconds(config.start_healing_time, TimerMode::Once),
+        },
+        Sprite {
+            image: asset_server.load("human.png"),
+            color: start_color,
+            custom_size: Some(Vec2::splat(PLAYER_SCALE * traits.size)),
+            ..default()
+        },
+        Energy {
+            energy: traits.max_energy,
+            max_energy: traits.max_energy,
+            timer: Timer::from_seconds(config.start_resting_time, TimerMode::Once),
+            resting: true,
+        },
+        Movement {
+            velocity: Vec2::new(0.0, 0.0),
+            last_x: position.x,
+            last_y: position.y,
+        },
+        brain,
+        Fitness(0.),
+        LifetimeStats::default(),
+        // Bundles top out at 15 components, so the steering state rides together
+        (Momentum::default(), Heading::default()),
+        Collider,
+        status_bars(),
+        BaseColor(start_color),
+        Transform::from_translation(position.extend(0.)),
+        Pickable {
+            should_block_lower: true,
+            is_hoverable: true, 
+        },
+    ))
+    .observe(|trigger: On<Pointer<Click>>, mut selected: ResMut<SelectedBobble>| {
+        // The inspector panel and network view follow the selection
+        selected.0 = Some(trigger.entity);
+    })
+    .observe(move |trigger: On<Pointer<Over>>, mut commands: Commands, mut query: Query<&mut Sprite>| {
+        if let Ok(mut sprite_handle) = query.get_mut(trigger.entity) {
+            sprite_handle.color = hover_color;
+            commands.entity(trigger.entity).insert(Highlighted);
+        }
+    })
+    .observe(|trigger: On<Pointer<Out>>, mut commands: Commands, mut query: Query<(&mut Sprite, &BaseColor)>| {
+        if let Ok((mut sprite_handle, base_color)) = query.get_mut(trigger.entity) {
+            sprite_handle.color = base_color.0;
+            commands.entity(trigger.entity).remove::<Highlighted>();
+        }
+    })
+    .insert_if(Thirst::full(), || THIRST_ENABLED)
+    .id()
+}
+
 fn setup_ui(mut commands: Commands) {
     commands.spawn((
         Text::new("Hunger: 100\nHealth: 100\nEnergy: 100"),
@@ -466,17 +651,21 @@ fn setup_ui(mut commands: Commands) {
 }
 
 fn update_ui(
-    mut text_query: Query<&mut Text>,
+    mut text_query: Query<&mut Text, (Without<EventFeed>, Without<Inspector>, Without<SpeciesLegend>)>,
     target_query: Single<(&Hunger, &Health, &Energy, &Movement), With<Target>>,
+    infected: Query<(), With<Infection>>,
+    immune: Query<(), With<Immune>>,
 ) {
     for mut text in text_query.iter_mut() {
         *text = Text::new(format!(
-            "Hunger {:.2} Health {:.2} Energy {:.2} Velocity ({:.2}, {:.2})", 
+            "Hunger {:.2} Health {:.2} Energy {:.2} Velocity ({:.2}, {:.2}) Infected {} Immune {}", 
             target_query.0.hunger, 
             target_query.1.health, 
             target_query.2.energy, 
             target_query.3.velocity.x, 
-            target_query.3.velocity.y
+            target_query.3.velocity.y,
+            infected.iter().count(),
+            immune.iter().count(),
         ));
     }
 }
@@ -505,6 +694,7 @@ fn update_camera(
 fn move_target(
     mut target: Single<&mut Transform, With<Target>>,
     time: Res<Time>,
+    config: Res<SimConfig>,
     kb_input: Res<ButtonInput<KeyCode>>,
 ) {
     let mut direction = Vec2::ZERO;
@@ -525,16 +715,23 @@ fn move_target(
         direction.x += 1.;
     }
 
-    let move_delta = direction.normalize_or_zero() * TARGET_SPEED * time.delta_secs();
+    let move_delta = direction.normalize_or_zero() * config.target_speed * time.delta_secs();
     target.translation += move_delta.extend(0.);
 }
 
 fn update_hunger(
     time: Res<Time>,
-    mut hunger_query: Query<&mut Hunger>
+    config: Res<SimConfig>,
+    game_time: Res<GameTime>,
+    mut hunger_query: Query<(&mut Hunger, &Bobble, Option<&Brain>, Has<Pregnant>)>
 ) {
-    hunger_query.iter_mut().for_each(|mut query| {
-        query.hunger -= HUNGER_RATE * time.delta_secs();
+    // Rises to night_hunger times as fast at midnight
+    let time_of_day = game_time.scale(&config, config.night_hunger);
+    hunger_query.iter_mut().for_each(|(mut query, bobble, brain, pregnant)| {
+        let drain = time_of_day * if pregnant { config.pregnancy_drain } else { 1. };
+        let metabolism = brain.map_or(0., |b| b.complexity as f32 * config.metabolism_cost);
+        let hunger_rate = bobble.size_scaled(config.hunger_rate, config.size_hunger);
+        query.hunger -= (hunger_rate * drain + metabolism) * time.delta_secs();
 
         if query.hunger <= 0. {
             query.hunger = 0.;
@@ -542,115 +739,311 @@ fn update_hunger(
     });
 }
 
+/// Ages every bobble by the game time that's passed.
+fn age_bobbles(time: Res<Time>, mut bobbles: Query<&mut Bobble>) {
+    for mut bobble in &mut bobbles {
+        bobble.age += time.delta_secs();
+    }
+}
+
 fn update_energy(
     time: Res<Time>,
-    mut targets: Query<(&mut Energy, &Movement), With<Bobble>>
+    config: Res<SimConfig>,
+    mut targets: Query<(&mut Energy, &Movement, &Bobble, Has<Pregnant>)>
 ) {
-    targets.iter_mut().for_each(|(mut energy, movement)| {
-        energy.timer.tick(time.delta());
+    targets.iter_mut().for_each(|(mut energy, movement, bobble, pregnant)| {
+        let speed = movement.velocity.length();
+        if energy.resting && speed > MOVE_SPEED {
+            energy.resting = false;
+        } else if !energy.resting && speed < REST_SPEED {
+            energy.resting = true;
+            energy.timer.reset();
+        }
 
         // If moving, lose energy? Proportional to speed?
-        if movement.velocity.abs().length() > f32::EPSILON {
-            energy.energy -= ENERGY_RATE * time.delta_secs();
-            energy.timer.reset();
+        if !energy.resting {
+            let drain = if pregnant { config.pregnancy_drain } else { 1. };
+            let cost = bobble.size_scaled(config.energy_rate, config.size_energy);
+            energy.energy = (energy.energy - cost * drain * time.delta_secs()).max(0.);
+            return;
         }
 
-        if energy.timer.is_finished() && energy.energy < energy.max_energy {
-            energy.energy += ENERGY_RATE * time.delta_secs();
+        // Resting long enough brings energy back at a steady rate
+        if energy.timer.tick(time.delta()).is_finished() {
+            energy.energy = (energy.energy + config.energy_rate * time.delta_secs()).min(energy.max_energy);
         }
     });
 }
 
 fn update_health(
     time: Res<Time>,
+    config: Res<SimConfig>,
     mut targets: Query<(&mut Health, &Hunger), With<Hunger>>
 ) {
     targets.iter_mut().for_each(|(mut health, hunger)| {
-        health.timer.tick(time.delta());
-
+        // Starving bobbles only ever lose health. Fed ones heal once nothing
+        // has hurt them for the whole grace period.
         if hunger.hunger <= 0. {
-            health.health -= HUNGER_RATE * time.delta_secs();
+            health.health -= config.hunger_rate * time.delta_secs();
             health.timer.reset();
+        } else if health.timer.tick(time.delta()).is_finished() {
+            health.health = (health.health + config.healing_rate * time.delta_secs()).min(health.max_health);
         }
 
+        // Other damage (thirst) lands before this runs, so check for death either way
         if health.health <= 0. {
             health.health = 0.;
             health.alive = false;
         }
+    });
+}
+
+/// Tracks each mover's velocity as an exponential moving average of how far
+/// it moved each step, so one odd step doesn't throw off energy drain. Also
+/// where bobbles have been, for their lifetime stats.
+fn update_velocity(
+    time: Res<Time>,
+    config: Res<SimConfig>,
+    wrap: Res<WorldWrap>,
+    mut targets: Query<(&Transform, &mut Movement, Option<&mut LifetimeStats>)>
+) {
+    targets.iter_mut().for_each(|(transform, mut movement, mut stats)| {
+        let position = transform.translation.truncate();
+        // Crossing a wrapped edge counts as the short step it really is
+        let delta = wrapped_offset(Vec2::new(movement.last_x, movement.last_y), position, wrap.0);
+        if delta.length() <= TELEPORT_DISTANCE {
+            let measured = delta / time.delta_secs();
+            movement.velocity = movement.velocity.lerp(measured, VELOCITY_SMOOTHING);
+            if let Some(stats) = stats.as_mut() {
+                stats.distance_traveled += delta.length();
+            }
+            // The average only ever approaches zero, so settle it once it's close
+            if movement.velocity.length() < 0.01 {
+                movement.velocity = Vec2::ZERO;
+            }
+        }
 
-        if health.timer.is_finished() && health.health < health.max_health {
-            health.health += HEALING_RATE * time.delta_secs();
+        if let Some(stats) = stats.as_mut() {
+            let cell = (position / config.exploration_cell_size.max(1.)).floor();
+            stats.visited_cells.insert((cell.x as i32, cell.y as i32));
         }
+
+        movement.last_x = position.x;
+        movement.last_y = position.y;
     });
 }
 
-fn update_velocity(
+/// Turns bobble sprites towards their velocity, easing in so they don't
+/// jitter. The sprite art faces up.
+fn face_movement(
     time: Res<Time>,
-    mut targets: Query<(&Transform, &mut Movement)>
+    mut bobbles: Query<(&Movement, &mut Transform), With<Bobble>>,
 ) {
-    targets.iter_mut().for_each(|(transform, mut movement)| {
-        let delta_x = transform.translation.x - movement.last_x;
-        let delta_y = transform.translation.y - movement.last_y;
-        movement.velocity.x = delta_x / time.delta_secs();
-        movement.velocity.y = delta_y / time.delta_secs();
-
-        movement.last_x = transform.translation.x;
-        movement.last_y = transform.translation.y;
+    bobbles.iter_mut().for_each(|(movement, mut transform)| {
+        if movement.velocity.length() < MIN_TURN_SPEED {
+            return;
+        }
+        let facing = Quat::from_rotation_z(movement.velocity.to_angle() - FRAC_PI_2);
+        transform.rotation.smooth_nudge(&facing, TURN_DECAY_RATE, time.delta_secs());
     });
 }
 
+/// Removes whatever died this step. Bobbles leave a corpse, after a death
+/// animation of `death_animation_time` seconds if that's above zero.
 fn despawn_dead(
     mut commands: Commands,
-    query: Query<(Entity, &Health), With<Health>>,
+    asset_server: Res<AssetServer>,
+    config: Res<SimConfig>,
+    mut events: ResMut<SimEvents>,
+    query: Query<(Entity, &Health, &Transform, Option<&Sprite>, Has<Bobble>), With<Health>>,
 ) {
-    query.iter().for_each(|(entity, entity_health)| {
+    query.iter().for_each(|(entity, entity_health, transform, sprite, is_bobble)| {
         if !entity_health.alive {
+            if is_bobble {
+                events.push(format!("Bobble {} died", entity));
+                match sprite {
+                    Some(sprite) if config.death_animation_time > 0. => {
+                        spawn_dying(&mut commands, sprite, transform, entity_health.max_health, config.death_animation_time);
+                    }
+                    _ => spawn_corpse(&mut commands, &asset_server, transform.translation.truncate(), entity_health.max_health),
+                }
+            }
             commands.entity(entity).despawn();
         }
     });
 }
 
+/// Any bobble touching something edible eats it. `Without<Edible>` keeps the
+/// bobble query clear of the edible transforms being moved.
 fn bobble_eating_collision(
-    mut edible_collider_query: Query<(&mut Transform, &Edible), (With<Collider>, With<Edible>)>,
-    mut bobble_collider_query: Query<(&Transform, &mut Hunger), (With<Collider>, With<Bobble>, With<Target>, Without<Edible>)>,
+    config: Res<SimConfig>,
+    wrap: Res<WorldWrap>,
+    layout: Res<SpawnLayout>,
+    mut events: ResMut<SimEvents>,
+    mut commands: Commands,
+    mut edible_collider_query: Query<(Entity, &mut Transform, &Sprite, &mut Edible, Option<&FoodType>, Option<&Poisonous>, Has<Corpse>), (With<Collider>, With<Edible>)>,
+    mut bobble_collider_query: Query<(Entity, &Bobble, &Transform, &Sprite, &mut Hunger, &mut Health, Option<&mut LifetimeStats>), (With<Collider>, Without<Edible>)>,
+    mut rng: ResMut<SimRng>,
 ) {
-    edible_collider_query.iter_mut().for_each(|(mut edible_transform, edible)| {
-        bobble_collider_query.iter_mut().for_each(|(bobble_transform, mut hunger)| {
-            let dist = edible_transform.translation.truncate().distance(bobble_transform.translation.truncate());
-            if dist < COLLISION_DISTANCE {
-                // "Despawn" eaten thing (Move it somewhere else)
-                let mut rng = rand::rng();
-                let x: f32 = rng.random_range(-500_f32..=500_f32);
-                let y: f32 = rng.random_range(-500_f32..=500_f32);
-
-                edible_transform.translation = Vec3::new(x, y, 0.);
-                
-                hunger.hunger += edible.nutrition_value;
+    edible_collider_query.iter_mut().for_each(|(edible_entity, mut edible_transform, edible_sprite, mut edible, food_type, poison, is_corpse)| {
+        let mut eaten = false;
+        let edible_radius = collision_radius(edible_sprite, &config);
+        bobble_collider_query.iter_mut().for_each(|(bobble, eater, bobble_transform, bobble_sprite, mut hunger, mut health, stats)| {
+            let dist = wrapped_distance(edible_transform.translation.truncate(), bobble_transform.translation.truncate(), wrap.0);
+            if dist < edible_radius + collision_radius(bobble_sprite, &config) && !eaten {
+                let nutrition = edible.nutrition_value * eater.traits.diet.gain(&config, is_corpse);
+                hunger.hunger += nutrition;
                 if hunger.hunger > hunger.max_hunger {
                     hunger.hunger = hunger.max_hunger;
                 }
+                if let Some(mut stats) = stats {
+                    stats.food_eaten += nutrition;
+                }
+
+                // Starving bobbles eat poison too; it can finish them off
+                if let Some(poison) = poison {
+                    let food = food_type.map_or("plant".to_string(), |f| format!("{:?}", f).to_lowercase());
+                    events.push(format!("Bobble {} ate a poisonous {}", bobble, food));
+                    health.health = (health.health - poison.damage).max(0.);
+                    health.alive = health.health > 0.;
+                    health.timer.reset();
+                }
+
+                if is_corpse {
+                    // Corpses are gone once eaten
+                    commands.entity(edible_entity).despawn();
+                    eaten = true;
+                } else {
+                    // "Despawn" eaten thing (Move it somewhere else). It regrows
+                    // as the same kind of plant, but not necessarily as filling.
+                    let rng = &mut rng.0;
+                    let position = layout.plants.sample(rng);
+                    edible_transform.translation = position.extend(0.);
+                    if let Some(food_type) = food_type {
+                        edible.nutrition_value = plant_nutrition(*food_type, poison.is_some(), rng);
+                    }
+                }
             }
         });
     });
 }
 
-fn bobble_reproducing_collision(
-    mut bobble_query: Query<(&Transform, &Hunger, &Health, &Energy, &Bobble), (With<Reproducing>, With<Bobble>)>
-) {
-    let mut combinations = bobble_query.iter_combinations_mut();
-    while let Some([
-            (transform1, hunger1, health1, energy1, bobble1), 
-            (transform2, hunger2, health2, energy2, bobble2)
-        ]) = combinations.fetch_next() {
-        if bobble1.gender != bobble2.gender { 
-            // One is male, one is female
-            let dist = transform1.translation.truncate().distance(transform2.translation.truncate());
-            if dist < COLLISION_DISTANCE {
-                // They're close enough...
-                let mut rng = rand::rng();
-                let reproductibility_score = 
-            }
-            // Now we combine them
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Where the startup scene puts its bobbles and plants, for `seed`.
+    fn startup_positions(seed: u64) -> (Vec<Vec2>, Vec<Vec2>) {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Image>()
+            .init_asset::<Mesh>()
+            .init_asset::<ColorMaterial>()
+            .insert_resource(SimConfig { seed: Some(seed), ..default() })
+            .init_resource::<Innovations>()
+            .init_resource::<SimRng>()
+            .init_resource::<SpawnLayout>()
+            .add_systems(Startup, setup_scene);
+        app.update();
+
+        let world = app.world_mut();
+        let bobbles = world.query_filtered::<&Transform, (With<Bobble>, Without<Target>)>()
+            .iter(world)
+            .map(|transform| transform.translation.truncate())
+            .collect();
+        let plants = world.query_filtered::<&Transform, With<Plant>>()
+            .iter(world)
+            .map(|transform| transform.translation.truncate())
+            .collect();
+        (bobbles, plants)
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_startup_positions() {
+        let (bobbles, plants) = startup_positions(7);
+        assert!(!bobbles.is_empty() && !plants.is_empty());
+        assert_eq!(startup_positions(7), (bobbles.clone(), plants));
+        assert_ne!(startup_positions(8).0, bobbles);
+    }
+
+    /// Runs `system` on `world` for `seconds` of fixed steps.
+    pub(crate) fn run_for<M>(world: &mut World, seconds: f32, system: impl IntoSystem<(), (), M> + Copy) {
+        use bevy::ecs::system::RunSystemOnce;
+        use std::time::Duration;
+
+        world.init_resource::<Time>();
+        for _ in 0..(seconds / SIM_STEP as f32).round() as usize {
+            world.resource_mut::<Time>().advance_by(Duration::from_secs_f64(SIM_STEP));
+            world.run_system_once(system).expect("the system's resources are there");
         }
     }
+
+    fn energy(energy: f32, resting: bool, config: &SimConfig) -> Energy {
+        Energy {
+            energy,
+            max_energy: 100.,
+            timer: Timer::from_seconds(config.start_resting_time, TimerMode::Once),
+            resting,
+        }
+    }
+
+    #[test]
+    fn energy_comes_back_while_resting_and_drains_while_moving() {
+        let config = SimConfig::default();
+        let mut world = World::new();
+        let bobble = || Bobble::new(BobbleGender::Male, 0, BobbleTraits::default());
+        let movement = |speed: f32| Movement { velocity: Vec2::new(speed, 0.), last_x: 0., last_y: 0. };
+        let dawdler = world.spawn((energy(50., true, &config), movement(REST_SPEED + 1.), bobble())).id();
+        let runner = world.spawn((energy(50., true, &config), movement(MOVE_SPEED + 1.), bobble())).id();
+        world.insert_resource(config.clone());
+
+        // Drifting between the two speeds isn't enough to count as moving
+        run_for(&mut world, config.start_resting_time + 5., update_energy);
+        assert!(world.get::<Energy>(dawdler).unwrap().energy > 50.);
+        let drained = world.get::<Energy>(runner).unwrap().energy;
+        assert!(drained < 50.);
+
+        // Or, once moving, as resting
+        world.get_mut::<Movement>(runner).unwrap().velocity.x = REST_SPEED + 1.;
+        run_for(&mut world, config.start_resting_time + 5., update_energy);
+        assert!(world.get::<Energy>(runner).unwrap().energy < drained);
+
+        // Stopped, an empty bobble is full again `max_energy / energy_rate`
+        // seconds after its rest kicks in
+        world.get_mut::<Movement>(runner).unwrap().velocity.x = 0.;
+        world.get_mut::<Energy>(runner).unwrap().energy = 0.;
+        let refill = config.start_resting_time + 100. / config.energy_rate;
+        run_for(&mut world, refill - 1., update_energy);
+        assert!(world.get::<Energy>(runner).unwrap().energy < 100.);
+        run_for(&mut world, 1., update_energy);
+        assert_eq!(world.get::<Energy>(runner).unwrap().energy, 100.);
+    }
+
+    #[test]
+    fn starving_bobbles_never_heal() {
+        let config = SimConfig::default();
+        let mut world = World::new();
+        let health = || Health {
+            health: 50.,
+            max_health: 100.,
+            alive: true,
+            timer: Timer::from_seconds(config.start_healing_time, TimerMode::Once),
+        };
+        let fed = world.spawn((health(), Hunger { max_hunger: 100., hunger: 50. })).id();
+        let starving = world.spawn((health(), Hunger { max_hunger: 100., hunger: 0. })).id();
+        world.insert_resource(config.clone());
+
+        run_for(&mut world, config.start_healing_time + 5., update_health);
+        assert!(world.get::<Health>(fed).unwrap().health > 50.);
+        let starved = world.get::<Health>(starving).unwrap();
+        assert!(starved.health < 50. && starved.alive);
+        let starved = starved.health;
+
+        // Once fed it still waits out the whole grace period before healing
+        world.get_mut::<Hunger>(starving).unwrap().hunger = 50.;
+        run_for(&mut world, config.start_healing_time - 1., update_health);
+        assert_eq!(world.get::<Health>(starving).unwrap().health, starved);
+        run_for(&mut world, 2., update_health);
+        assert!(world.get::<Health>(starving).unwrap().health > starved);
+    }
 }
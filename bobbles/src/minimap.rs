@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::{Bobble, Plant, WORLD_SIZE};
+
+/// Size of the minimap in screen pixels.
+const MAP_SIZE: f32 = 180.;
+const MAP_MARGIN: f32 = 12.;
+const BOBBLE_DOT: f32 = 2.5;
+const PLANT_DOT: f32 = 1.5;
+
+/// Whether the minimap is drawn. Toggled with M.
+#[derive(Resource)]
+pub struct Minimap {
+    pub visible: bool,
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Self { visible: true }
+    }
+}
+
+pub fn toggle_minimap(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut minimap: ResMut<Minimap>,
+) {
+    if kb_input.just_pressed(KeyCode::KeyM) {
+        minimap.visible = !minimap.visible;
+    }
+}
+
+/// Draws the whole world scaled down in the top-left corner of the screen:
+/// a dot per bobble in its current sprite color, a dot per plant, and a
+/// rectangle for what the camera can see.
+pub fn draw_minimap(
+    mut gizmos: Gizmos,
+    minimap: Res<Minimap>,
+    bobbles: Query<(&Transform, &Sprite), With<Bobble>>,
+    plants: Query<(&Transform, &Sprite), (With<Plant>, Without<Bobble>)>,
+    camera: Single<(&Transform, &Projection), With<Camera2d>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+) {
+    if !minimap.visible {
+        return;
+    }
+
+    // Gizmos live in world space, so pin the map to the camera's view
+    let (camera_transform, projection) = *camera;
+    let scale = match projection {
+        Projection::Orthographic(ortho) => ortho.scale,
+        _ => 1.,
+    };
+    let camera_center = camera_transform.translation.truncate();
+    let half_view = Vec2::new(window.width(), window.height()) * 0.5 * scale;
+    let size = MAP_SIZE * scale;
+    let margin = MAP_MARGIN * scale;
+    let center = camera_center + Vec2::new(-half_view.x + margin + size / 2., half_view.y - margin - size / 2.);
+    let to_map = |world: Vec2| center + world / WORLD_SIZE * size;
+
+    gizmos.rect_2d(center, Vec2::splat(size), Color::srgba(1., 1., 1., 0.4));
+
+    for (transform, sprite) in &plants {
+        gizmos.circle_2d(to_map(transform.translation.truncate()), PLANT_DOT * scale, sprite.color);
+    }
+    for (transform, sprite) in &bobbles {
+        gizmos.circle_2d(to_map(transform.translation.truncate()), BOBBLE_DOT * scale, sprite.color);
+    }
+
+    gizmos.rect_2d(to_map(camera_center), half_view * 2. / WORLD_SIZE * size, Color::srgba(1., 1., 0.4, 0.8));
+}
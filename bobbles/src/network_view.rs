@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use neat::{Float, NeuralNetwork, NodeType};
+
+use crate::brain::{from_network, Brain};
+use crate::{SelectedBobble, WEIGHT_CAP};
+
+/// Size of the network panel in screen pixels.
+const PANEL_SIZE: Vec2 = Vec2::new(320., 200.);
+const PANEL_MARGIN: f32 = 12.;
+const NODE_RADIUS: f32 = 6.;
+
+/// Whether the selected bobble's brain is drawn. Toggled with N.
+#[derive(Resource)]
+pub struct NetworkView {
+    pub visible: bool,
+}
+
+impl Default for NetworkView {
+    fn default() -> Self {
+        Self { visible: true }
+    }
+}
+
+pub fn toggle_network_view(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut view: ResMut<NetworkView>,
+) {
+    if kb_input.just_pressed(KeyCode::KeyN) {
+        view.visible = !view.visible;
+    }
+}
+
+/// Draws the selected bobble's network in the top-right corner of the screen.
+/// Edges are green/red by sign and fade with magnitude; nodes go from blue to
+/// red with their current activation.
+pub fn draw_network_view(
+    mut gizmos: Gizmos,
+    view: Res<NetworkView>,
+    selected: Res<SelectedBobble>,
+    brains: Query<&Brain>,
+    camera: Single<(&Transform, &Projection), With<Camera2d>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+) {
+    if !view.visible {
+        return;
+    }
+    let Some(brain) = selected.0.and_then(|entity| brains.get(entity).ok()) else {
+        return;
+    };
+
+    // Gizmos live in world space, so pin the panel to the camera's view
+    let (camera_transform, projection) = *camera;
+    let scale = match projection {
+        Projection::Orthographic(ortho) => ortho.scale,
+        _ => 1.,
+    };
+    let half_view = Vec2::new(window.width(), window.height()) * 0.5 * scale;
+    let size = PANEL_SIZE * scale;
+    let margin = PANEL_MARGIN * scale;
+    let corner = camera_transform.translation.truncate() + half_view - size - Vec2::splat(margin);
+
+    gizmos.rect_2d(corner + size / 2., size, Color::srgba(1., 1., 1., 0.4));
+
+    let network = &brain.network;
+    let positions = layout(network, corner, size);
+
+    for (to_idx, node) in network.nodes.iter().enumerate() {
+        for &(from_idx, weight) in node.incoming.iter().chain(&node.recurrent) {
+            gizmos.line_2d(positions[from_idx], positions[to_idx], edge_color(weight));
+        }
+    }
+
+    for (idx, node) in network.nodes.iter().enumerate() {
+        gizmos.circle_2d(positions[idx], NODE_RADIUS * scale, node_color(node.value));
+    }
+}
+
+/// Places nodes in columns by their longest forward path from the inputs, with
+/// the outputs always in the last column.
+fn layout(network: &NeuralNetwork, corner: Vec2, size: Vec2) -> Vec<Vec2> {
+    let mut depth: Vec<usize> = network.nodes.iter()
+        .map(|n| if n.node_type == NodeType::Input { 0 } else { 1 })
+        .collect();
+    for &idx in network.execution_order() {
+        depth[idx] = network.nodes[idx].incoming.iter()
+            .map(|(from_idx, _)| depth[*from_idx] + 1)
+            .max()
+            .unwrap_or(1);
+    }
+    let last_column = depth.iter().copied().max().unwrap_or(0).max(1);
+    for (idx, node) in network.nodes.iter().enumerate() {
+        if node.node_type == NodeType::Output {
+            depth[idx] = last_column;
+        }
+    }
+
+    let mut columns: Vec<Vec<usize>> = vec![Vec::new(); last_column + 1];
+    for (idx, &d) in depth.iter().enumerate() {
+        columns[d].push(idx);
+    }
+
+    let mut positions = vec![Vec2::ZERO; network.nodes.len()];
+    for (column, members) in columns.iter().enumerate() {
+        let x = corner.x + size.x * (column as f32 + 0.5) / (last_column + 1) as f32;
+        for (row, &idx) in members.iter().enumerate() {
+            let y = corner.y + size.y * (row as f32 + 1.) / (members.len() + 1) as f32;
+            positions[idx] = Vec2::new(x, y);
+        }
+    }
+    positions
+}
+
+fn edge_color(weight: Float) -> Color {
+    let alpha = from_network(0.15 + 0.85 * (weight.abs() / WEIGHT_CAP).min(1.));
+    if weight >= 0. {
+        Color::srgba(0.2, 1., 0.2, alpha)
+    } else {
+        Color::srgba(1., 0.2, 0.2, alpha)
+    }
+}
+
+fn node_color(value: Float) -> Color {
+    let v = from_network(value.clamp(-1., 1.));
+    Color::srgb(0.5 + 0.5 * v, 0.5, 0.5 - 0.5 * v)
+}
@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+
+use crate::config::SimConfig;
+use crate::sim_rng::SimRng;
+use crate::spatial::SpatialIndex;
+use crate::world::{wrapped_offset, SpawnDistribution, WorldWrap};
+use crate::{Bobble, Collider, PLAYER_SCALE};
+
+const OBSTACLE_COLOR: Color = Color::srgb(0.45, 0.42, 0.38);
+/// How close a bobble's centre can get to an obstacle's edge.
+const BOBBLE_RADIUS: f32 = PLAYER_SCALE / 4.;
+
+/// A rock bobbles can't walk through or see past.
+#[derive(Component)]
+pub struct Obstacle {
+    pub radius: f32,
+}
+
+pub fn spawn_obstacles(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    config: Res<SimConfig>,
+    mut rng: ResMut<SimRng>,
+) {
+    let rng = &mut rng.0;
+    let mesh = meshes.add(Circle::new(config.obstacle_radius));
+    let material = materials.add(OBSTACLE_COLOR);
+    for _ in 0..config.obstacle_count {
+        commands.spawn((
+            Obstacle {
+                radius: config.obstacle_radius,
+            },
+            Collider,
+            Mesh2d(mesh.clone()),
+            MeshMaterial2d(material.clone()),
+            // Between the world plane and the bobbles
+            Transform::from_translation(SpawnDistribution::Uniform.sample(rng).extend(-0.25)),
+        ));
+    }
+}
+
+/// Pushes any bobble that moved into an obstacle back out to its edge, along
+/// the line from the obstacle's centre. Sliding along is all that's left of
+/// the movement, so a bobble heading straight in just stops.
+pub fn push_out_of_obstacles(
+    wrap: Res<WorldWrap>,
+    index: Res<SpatialIndex>,
+    mut bobbles: Query<&mut Transform, With<Bobble>>,
+) {
+    let reach = index.widest_obstacle + BOBBLE_RADIUS;
+    bobbles.iter_mut().for_each(|mut transform| {
+        let nearby: Vec<(f32, Vec2)> = index.obstacles.near(transform.translation.truncate(), reach, wrap.0).collect();
+        for (radius, center) in nearby {
+            let offset = wrapped_offset(center, transform.translation.truncate(), wrap.0);
+            let min_distance = radius + BOBBLE_RADIUS;
+            if offset.length() < min_distance {
+                // Dead centre has no way out, so pick one
+                let out = offset.try_normalize().unwrap_or(Vec2::X);
+                let correction = out * min_distance - offset;
+                transform.translation += correction.extend(0.);
+            }
+        }
+    });
+}
@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+
+use crate::brain::Brain;
+use crate::config::SimConfig;
+use crate::world::WorldWrap;
+use crate::WORLD_SIZE;
+
+/// A scent left on the world plane, stored as one level per cell of a square
+/// grid over it. Bobbles lay it down wherever they go; it spreads out to
+/// neighbouring cells and fades away over time.
+#[derive(Resource, Default)]
+pub struct Pheromones {
+    resolution: usize,
+    levels: Vec<f32>,
+}
+
+impl Pheromones {
+    /// Which cell `position` falls in, as (column, row). Positions off the
+    /// plane count as being in the nearest edge cell.
+    fn cell(&self, position: Vec2) -> (isize, isize) {
+        let cell_size = WORLD_SIZE / self.resolution as f32;
+        let max = self.resolution as isize - 1;
+        let cell = ((position + WORLD_SIZE / 2.) / cell_size).floor();
+        ((cell.x as isize).clamp(0, max), (cell.y as isize).clamp(0, max))
+    }
+
+    /// The level at a cell. Off the grid, it wraps round to the other side on
+    /// a wrapping world and repeats the edge cell otherwise.
+    fn level(&self, column: isize, row: isize, wrap: bool) -> f32 {
+        let n = self.resolution as isize;
+        let (column, row) = if wrap {
+            (column.rem_euclid(n), row.rem_euclid(n))
+        } else {
+            (column.clamp(0, n - 1), row.clamp(0, n - 1))
+        };
+        self.levels[(row * n + column) as usize]
+    }
+
+    fn deposit(&mut self, position: Vec2, amount: f32) {
+        let (column, row) = self.cell(position);
+        self.levels[row as usize * self.resolution + column as usize] += amount;
+    }
+
+    /// Which way the scent gets stronger from `position` and how strong it
+    /// is there, squashed into 0 to 1. Reads as no direction and nothing
+    /// before the grid exists.
+    pub fn sense(&self, position: Vec2, wrap: bool) -> (Vec2, f32) {
+        if self.levels.is_empty() {
+            return (Vec2::ZERO, 0.);
+        }
+        let (column, row) = self.cell(position);
+        let gradient = Vec2::new(
+            self.level(column + 1, row, wrap) - self.level(column - 1, row, wrap),
+            self.level(column, row + 1, wrap) - self.level(column, row - 1, wrap),
+        );
+        let level = self.level(column, row, wrap);
+        (gradient.normalize_or_zero(), level / (1. + level))
+    }
+}
+
+/// Adds every brain-driven bobble's scent to the cell it's standing in,
+/// (re)building the grid first if its resolution changed.
+pub fn deposit_pheromones(
+    time: Res<Time>,
+    config: Res<SimConfig>,
+    mut pheromones: ResMut<Pheromones>,
+    bobbles: Query<&Transform, With<Brain>>,
+) {
+    let resolution = config.pheromone_resolution.max(1);
+    if pheromones.resolution != resolution {
+        *pheromones = Pheromones {
+            resolution,
+            levels: vec![0.; resolution * resolution],
+        };
+    }
+
+    let amount = config.pheromone_deposit * time.delta_secs();
+    for transform in &bobbles {
+        pheromones.deposit(transform.translation.truncate(), amount);
+    }
+}
+
+/// Moves each cell's level part of the way toward the average of its four
+/// neighbours, then fades everything by `pheromone_decay`.
+pub fn spread_pheromones(
+    time: Res<Time>,
+    config: Res<SimConfig>,
+    wrap: Res<WorldWrap>,
+    mut pheromones: ResMut<Pheromones>,
+) {
+    if pheromones.levels.is_empty() {
+        return;
+    }
+    let dt = time.delta_secs();
+    let diffusion = (config.pheromone_diffusion * dt).clamp(0., 1.);
+    let keep = (1. - config.pheromone_decay * dt).max(0.);
+
+    let n = pheromones.resolution as isize;
+    let mut levels = Vec::with_capacity(pheromones.levels.len());
+    for row in 0..n {
+        for column in 0..n {
+            let level = pheromones.level(column, row, wrap.0);
+            let neighbours = (pheromones.level(column - 1, row, wrap.0)
+                + pheromones.level(column + 1, row, wrap.0)
+                + pheromones.level(column, row - 1, wrap.0)
+                + pheromones.level(column, row + 1, wrap.0)) / 4.;
+            levels.push((level + diffusion * (neighbours - level)) * keep);
+        }
+    }
+    pheromones.levels = levels;
+}
@@ -0,0 +1,76 @@
+use bevy::post_process::bloom::Bloom;
+use bevy::prelude::*;
+
+use crate::brain::Brain;
+use crate::config::{PulseMetric, SimConfig};
+use crate::{Bobble, Energy};
+
+/// How quickly the bloom follows the metric, per second, so it swells and
+/// fades rather than flickering.
+const PULSE_SMOOTHING: f32 = 2.;
+/// How quickly the birth rate estimate follows births, per second.
+const BIRTH_RATE_SMOOTHING: f32 = 0.5;
+
+/// Whether the camera's bloom follows the health of the ecosystem, which
+/// `PulseMetric` measures. Toggled with P; off leaves the usual glow.
+#[derive(Resource)]
+pub struct BloomPulse {
+    pub enabled: bool,
+    /// Births per second lately, as a moving average.
+    birth_rate: f32,
+}
+
+impl Default for BloomPulse {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            birth_rate: 0.,
+        }
+    }
+}
+
+pub fn toggle_bloom_pulse(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut pulse: ResMut<BloomPulse>,
+) {
+    if kb_input.just_pressed(KeyCode::KeyP) {
+        pulse.enabled = !pulse.enabled;
+    }
+}
+
+/// Eases the bloom intensity toward where the chosen metric, from 0 to 1,
+/// puts it between `bloom_min` and `bloom_max`.
+pub fn pulse_bloom(
+    time: Res<Time>,
+    config: Res<SimConfig>,
+    mut pulse: ResMut<BloomPulse>,
+    mut bloom: Single<&mut Bloom>,
+    bobbles: Query<&Energy, With<Brain>>,
+    newborns: Query<&Bobble, Added<Bobble>>,
+) {
+    let dt = time.delta_secs();
+    // Generation 0 are spawned or reseeded, not born
+    let births = newborns.iter().filter(|bobble| bobble.generation > 0).count() as f32;
+    if dt > 0. {
+        let rate = pulse.birth_rate + (births / dt - pulse.birth_rate) * (BIRTH_RATE_SMOOTHING * dt).min(1.);
+        pulse.birth_rate = rate;
+    }
+
+    let target = if pulse.enabled {
+        let level = match config.pulse_metric {
+            PulseMetric::Energy => {
+                let count = bobbles.iter().count();
+                if count > 0 {
+                    bobbles.iter().map(|energy| energy.energy / energy.max_energy).sum::<f32>() / count as f32
+                } else {
+                    0.
+                }
+            }
+            PulseMetric::BirthRate => pulse.birth_rate / config.pulse_birth_rate.max(f32::EPSILON),
+        };
+        config.bloom_min + (config.bloom_max - config.bloom_min) * level.clamp(0., 1.)
+    } else {
+        Bloom::NATURAL.intensity
+    };
+    bloom.intensity += (target - bloom.intensity) * (PULSE_SMOOTHING * dt).min(1.);
+}
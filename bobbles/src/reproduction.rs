@@ -0,0 +1,383 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use neat::{Genome, WeightInit};
+use rand::Rng;
+
+use crate::ancestry::{Ancestry, LineageId};
+use crate::brain::{Brain, Innovations};
+use crate::config::{ReproductionMode, SexDetermination, SimConfig};
+use crate::daynight::GameTime;
+use crate::events::SimEvents;
+use crate::sim_rng::SimRng;
+use crate::spatial::SpatialIndex;
+use crate::world::{wrapped_distance, WorldWrap};
+use crate::{
+    collision_radius, spawn_bobble, Bobble, BobbleGender, BobbleTraits, Energy, Fitness, Health, Hunger,
+    LifetimeStats, Reproducing, Target, MUTATION_RATES, RECURRENT_CONNECTIONS, WEIGHT_CAP,
+};
+
+/// Carried by a female between mating and giving birth. Holds what the father
+/// contributes to the child.
+#[derive(Component)]
+pub struct Pregnant {
+    pub timer: Timer,
+    father_genome: Genome,
+    father_fitness: f64,
+    father_traits: BobbleTraits,
+    father_generation: u32,
+    father_lineage: Option<LineageId>,
+}
+
+/// Carried by a bobble that mated or budded recently, until it may
+/// reproduce again.
+#[derive(Component)]
+pub struct ReproductionCooldown(Timer);
+
+/// What bringing a child into the world needs to look at.
+#[derive(SystemParam)]
+pub struct Nursery<'w> {
+    asset_server: Res<'w, AssetServer>,
+    config: Res<'w, SimConfig>,
+    game_time: Res<'w, GameTime>,
+    ancestry: ResMut<'w, Ancestry>,
+}
+
+impl Nursery<'_> {
+    /// The sex of a child born now with `traits`, by the configured rule.
+    fn child_gender(&self, traits: &BobbleTraits, rng: &mut impl Rng) -> BobbleGender {
+        let config = &self.config;
+        let male_chance = match config.sex_determination {
+            SexDetermination::Random => config.male_ratio,
+            SexDetermination::Inherited => config.male_ratio + traits.sex_bias,
+            SexDetermination::Temperature => {
+                let warmth = self.game_time.daylight(config.day_length) as f64 * 2. - 1.;
+                config.male_ratio + config.temperature_sex_bias * warmth
+            }
+        };
+        BobbleGender::random(male_chance, rng)
+    }
+
+    /// Gives a newborn `child` its lineage id, logging its birth to `parents`.
+    fn record_birth(&mut self, commands: &mut Commands, child: Entity, parents: &[LineageId], generation: u32) {
+        let id = self.ancestry.record(parents, generation, self.game_time.elapsed);
+        commands.entity(child).insert(id);
+    }
+}
+
+/// How fit a bobble is to reproduce right now, from 0 to 1.
+fn condition(hunger: &Hunger, health: &Health, energy: &Energy) -> f32 {
+    (hunger.hunger / hunger.max_hunger
+        + health.health / health.max_health
+        + energy.energy / energy.max_energy) / 3.
+}
+
+pub fn sexual_reproduction(config: Res<SimConfig>) -> bool {
+    config.reproduction_mode == ReproductionMode::Sexual
+}
+
+pub fn asexual_reproduction(config: Res<SimConfig>) -> bool {
+    config.reproduction_mode == ReproductionMode::Asexual
+}
+
+/// Marks well-fed, rested, old-enough bobbles as `Reproducing` and unmarks
+/// them once they no longer qualify. Pregnant bobbles and ones that
+/// reproduced recently never qualify.
+pub fn update_mating_readiness(
+    mut commands: Commands,
+    config: Res<SimConfig>,
+    bobbles: Query<(Entity, &Bobble, &Hunger, &Energy, Has<Reproducing>, Has<Pregnant>, Has<ReproductionCooldown>)>,
+) {
+    bobbles.iter().for_each(|(entity, bobble, hunger, energy, reproducing, pregnant, cooling_down)| {
+        let ready = !pregnant
+            && !cooling_down
+            && bobble.age >= config.mating_min_age
+            && hunger.hunger / hunger.max_hunger >= config.mating_min_hunger
+            && energy.energy / energy.max_energy >= config.mating_min_energy;
+
+        if ready && !reproducing {
+            commands.entity(entity).insert(Reproducing {});
+        } else if !ready && reproducing {
+            commands.entity(entity).remove::<Reproducing>();
+        }
+    });
+}
+
+/// Steers each `Reproducing` bobble toward the nearest opposite-gender one, on
+/// top of whatever its brain is doing, so mates actually meet.
+pub fn seek_mates(
+    time: Res<Time>,
+    config: Res<SimConfig>,
+    wrap: Res<WorldWrap>,
+    index: Res<SpatialIndex>,
+    mut seekers: Query<(Entity, &mut Transform, &Bobble), (With<Reproducing>, Without<Target>)>,
+) {
+    let genders: HashMap<Entity, BobbleGender> = seekers.iter().map(|(entity, _, bobble)| (entity, bobble.gender)).collect();
+
+    seekers.iter_mut().for_each(|(_, mut transform, bobble)| {
+        let position = transform.translation.truncate();
+        let nearest_mate = index.bobbles.nearest(position, f32::INFINITY, wrap.0, |mate| {
+            genders.get(mate).is_some_and(|gender| *gender != bobble.gender)
+        });
+
+        if let Some((_, offset)) = nearest_mate {
+            // Don't overshoot once they're on top of each other
+            let step = (config.mate_seek_speed * time.delta_secs()).min(offset.length());
+            transform.translation += (offset.normalize_or_zero() * step).extend(0.);
+        }
+    });
+}
+
+/// Mates nearby opposite-gender bobbles that are both looking to reproduce.
+/// The female becomes `Pregnant`; the child only arrives once that runs out.
+/// Both parents are credited with the child straight away, then cool down
+/// before mating again. Each bobble mates at most once a step, since the
+/// components marking it as taken only land once the step is over.
+pub fn bobble_reproducing_collision(
+    mut commands: Commands,
+    config: Res<SimConfig>,
+    mut events: ResMut<SimEvents>,
+    wrap: Res<WorldWrap>,
+    bobble_query: Query<(Entity, &Transform, &Hunger, &Health, &Energy, &Bobble, &Brain, &Fitness, &Sprite, Has<Pregnant>, Option<&LineageId>), With<Reproducing>>,
+    mut stats: Query<&mut LifetimeStats>,
+    mut rng: ResMut<SimRng>,
+) {
+    let rng = &mut rng.0;
+    let mut mated = HashSet::new();
+    for [first, second] in bobble_query.iter_combinations() {
+        // One is male, one is female
+        let (mother, father) = match (first.5.gender, second.5.gender) {
+            (BobbleGender::Female, BobbleGender::Male) => (first, second),
+            (BobbleGender::Male, BobbleGender::Female) => (second, first),
+            _ => continue,
+        };
+        let (mother, mother_transform, mother_hunger, mother_health, mother_energy, _, _, _, mother_sprite, pregnant, _) = mother;
+        let (father, father_transform, father_hunger, father_health, father_energy, father_bobble, father_brain, father_fitness, father_sprite, _, father_lineage) = father;
+        if pregnant || mated.contains(&mother) || mated.contains(&father) {
+            continue;
+        }
+
+        let dist = wrapped_distance(mother_transform.translation.truncate(), father_transform.translation.truncate(), wrap.0);
+        if dist < collision_radius(mother_sprite, &config) + collision_radius(father_sprite, &config) {
+            // They're close enough... healthier, better-fed, more rested pairs
+            // conceive more reliably
+            let reproductibility_score = (condition(mother_hunger, mother_health, mother_energy)
+                + condition(father_hunger, father_health, father_energy)) / 2.;
+            if rng.random::<f32>() < reproductibility_score {
+                commands.entity(mother).insert(Pregnant {
+                    timer: Timer::from_seconds(config.pregnancy_time, TimerMode::Once),
+                    father_genome: father_brain.genome.clone(),
+                    father_fitness: father_fitness.0,
+                    father_traits: father_bobble.traits,
+                    father_generation: father_bobble.generation,
+                    father_lineage: father_lineage.copied(),
+                });
+                for parent in [mother, father] {
+                    if let Ok(mut parent_stats) = stats.get_mut(parent) {
+                        parent_stats.offspring_count += 1;
+                    }
+                    mated.insert(parent);
+                    commands.entity(parent)
+                        .remove::<Reproducing>()
+                        .insert(ReproductionCooldown(Timer::from_seconds(config.mating_cooldown, TimerMode::Once)));
+                }
+                events.push(format!("Bobbles {} and {} mated", mother, father));
+            }
+        }
+    }
+}
+
+/// Counts pregnancies down and spawns the child when one finishes. The child
+/// takes a mutated copy of one parent's brain, the fitter one more often, and
+/// traits halfway between both.
+pub fn update_pregnancies(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut nursery: Nursery,
+    mut events: ResMut<SimEvents>,
+    mut innovations: ResMut<Innovations>,
+    mut mothers: Query<(Entity, &mut Pregnant, &Transform, &Bobble, &Brain, &Fitness, Option<&LineageId>)>,
+    mut rng: ResMut<SimRng>,
+) {
+    let rng = &mut rng.0;
+    for (mother, mut pregnant, transform, bobble, brain, fitness, lineage) in mothers.iter_mut() {
+        if !pregnant.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let total = fitness.0 + pregnant.father_fitness;
+        let mother_share = if total > 0. { fitness.0 / total } else { 0.5 };
+        let mut genome = if rng.random_bool(mother_share.clamp(0., 1.)) {
+            brain.genome.clone()
+        } else {
+            pregnant.father_genome.clone()
+        };
+        genome.fitness = 0.;
+        genome.mutate_with_rng(&mut innovations, &MUTATION_RATES, WEIGHT_CAP, RECURRENT_CONNECTIONS, WeightInit::default(), rng);
+
+        let traits = bobble.traits.blend(&pregnant.father_traits, rng).mutate(&nursery.config, rng);
+        let generation = bobble.generation.max(pregnant.father_generation) + 1;
+        let gender = nursery.child_gender(&traits, rng);
+
+        let child = spawn_bobble(
+            &mut commands,
+            &nursery.asset_server,
+            &nursery.config,
+            transform.translation.truncate(),
+            Bobble::new(gender, generation, traits),
+            Brain::new(genome),
+        );
+        let parents: Vec<LineageId> = lineage.copied().into_iter().chain(pregnant.father_lineage).collect();
+        nursery.record_birth(&mut commands, child, &parents, generation);
+        events.push(format!("Bobble {} gave birth to {} (generation {})", mother, child, generation));
+        commands.entity(mother).remove::<Pregnant>();
+    }
+}
+
+/// Spawns a mutated clone of every `Reproducing` bobble next to it, paid for
+/// out of its hunger and energy, then makes it wait before budding again.
+pub fn bud_offspring(
+    mut commands: Commands,
+    mut nursery: Nursery,
+    mut events: ResMut<SimEvents>,
+    mut innovations: ResMut<Innovations>,
+    mut parents: Query<(Entity, &Transform, &Bobble, &Brain, &mut Hunger, &mut Energy, &mut LifetimeStats, Option<&LineageId>), With<Reproducing>>,
+    mut rng: ResMut<SimRng>,
+) {
+    let rng = &mut rng.0;
+    for (parent, transform, bobble, brain, mut hunger, mut energy, mut stats, lineage) in parents.iter_mut() {
+        let mut genome = brain.genome.clone();
+        genome.fitness = 0.;
+        genome.mutate_with_rng(&mut innovations, &MUTATION_RATES, WEIGHT_CAP, RECURRENT_CONNECTIONS, WeightInit::default(), rng);
+
+        let config = &nursery.config;
+        let traits = bobble.traits.mutate(config, rng);
+        hunger.hunger = (hunger.hunger - config.budding_cost * hunger.max_hunger).max(0.);
+        energy.energy = (energy.energy - config.budding_cost * energy.max_energy).max(0.);
+        stats.offspring_count += 1;
+
+        let generation = bobble.generation + 1;
+        let gender = nursery.child_gender(&traits, rng);
+        let child = spawn_bobble(
+            &mut commands,
+            &nursery.asset_server,
+            config,
+            transform.translation.truncate(),
+            Bobble::new(gender, generation, traits),
+            Brain::new(genome),
+        );
+        events.push(format!("Bobble {} budded off {} (generation {})", parent, child, generation));
+        commands.entity(parent)
+            .remove::<Reproducing>()
+            .insert(ReproductionCooldown(Timer::from_seconds(config.budding_cooldown, TimerMode::Once)));
+        let parents: Vec<LineageId> = lineage.copied().into_iter().collect();
+        nursery.record_birth(&mut commands, child, &parents, generation);
+    }
+}
+
+pub fn update_reproduction_cooldowns(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut bobbles: Query<(Entity, &mut ReproductionCooldown)>,
+) {
+    for (entity, mut cooldown) in bobbles.iter_mut() {
+        if cooldown.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).remove::<ReproductionCooldown>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use neat::InnovationHistory;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+    use crate::tests::run_for;
+    use crate::SIM_STEP;
+
+    /// A grown, fed and rested bobble of `gender` at the origin, looking to mate.
+    fn mate(gender: BobbleGender, rng: &mut StdRng) -> impl Bundle {
+        let mut bobble = Bobble::new(gender, 0, BobbleTraits::default());
+        bobble.age = 100.;
+        (
+            bobble,
+            Transform::default(),
+            Hunger { max_hunger: 100., hunger: 100. },
+            Health { health: 100., max_health: 100., alive: true, timer: Timer::from_seconds(1., TimerMode::Once) },
+            Energy { energy: 100., max_energy: 100., timer: Timer::from_seconds(1., TimerMode::Once), resting: true },
+            Brain::random(&mut InnovationHistory::default(), rng),
+            Fitness(0.),
+            Sprite { custom_size: Some(Vec2::splat(64.)), ..default() },
+            LifetimeStats::default(),
+            Reproducing {},
+        )
+    }
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.insert_resource(SimConfig::default());
+        world.insert_resource(SimEvents::default());
+        world.insert_resource(WorldWrap(false));
+        world.insert_resource(SimRng(StdRng::seed_from_u64(0)));
+        world
+    }
+
+    #[test]
+    fn a_bobble_mates_once_a_step() {
+        let mut world = world();
+        let mut rng = StdRng::seed_from_u64(0);
+        let mother = world.spawn(mate(BobbleGender::Female, &mut rng)).id();
+        let fathers = [world.spawn(mate(BobbleGender::Male, &mut rng)).id(), world.spawn(mate(BobbleGender::Male, &mut rng)).id()];
+
+        run_for(&mut world, SIM_STEP as f32, bobble_reproducing_collision);
+        assert!(world.get::<Pregnant>(mother).is_some());
+        let cooling = fathers.iter().filter(|&&f| world.get::<ReproductionCooldown>(f).is_some()).count();
+        assert_eq!(cooling, 1);
+    }
+
+    #[test]
+    fn a_crowd_mates_no_more_than_once_a_bobble_a_step() {
+        let mut world = world();
+        let mut rng = StdRng::seed_from_u64(0);
+        let mothers: Vec<Entity> = (0..8).map(|_| world.spawn(mate(BobbleGender::Female, &mut rng)).id()).collect();
+        let fathers: Vec<Entity> = (0..12).map(|_| world.spawn(mate(BobbleGender::Male, &mut rng)).id()).collect();
+        let offspring = |world: &World, bobbles: &[Entity]| -> Vec<u32> {
+            bobbles.iter().map(|&b| world.get::<LifetimeStats>(b).unwrap().offspring_count).collect()
+        };
+
+        for step in 1..=5 {
+            // Everyone is ready again each step, so only the once-a-step rule holds them back
+            for &bobble in mothers.iter().chain(&fathers) {
+                world.entity_mut(bobble).remove::<(Pregnant, ReproductionCooldown)>().insert(Reproducing {});
+            }
+            run_for(&mut world, SIM_STEP as f32, bobble_reproducing_collision);
+
+            let conceived = mothers.iter().filter(|&&m| world.get::<Pregnant>(m).is_some()).count();
+            assert!(conceived > 0);
+            let (mothered, fathered) = (offspring(&world, &mothers), offspring(&world, &fathers));
+            assert!(mothered.iter().chain(&fathered).all(|&count| count <= step));
+            assert_eq!(mothered.iter().sum::<u32>(), fathered.iter().sum::<u32>());
+        }
+    }
+
+    #[test]
+    fn mating_waits_out_the_cooldown() {
+        let mut world = world();
+        let mut rng = StdRng::seed_from_u64(0);
+        let cooldown = world.resource::<SimConfig>().mating_cooldown;
+        let bobble = world.spawn(mate(BobbleGender::Male, &mut rng)).id();
+        world.entity_mut(bobble)
+            .remove::<Reproducing>()
+            .insert(ReproductionCooldown(Timer::from_seconds(cooldown, TimerMode::Once)));
+
+        run_for(&mut world, cooldown - 1., update_reproduction_cooldowns);
+        run_for(&mut world, SIM_STEP as f32, update_mating_readiness);
+        assert!(world.get::<Reproducing>(bobble).is_none());
+        run_for(&mut world, 2., update_reproduction_cooldowns);
+        run_for(&mut world, SIM_STEP as f32, update_mating_readiness);
+        assert!(world.get::<Reproducing>(bobble).is_some());
+    }
+}
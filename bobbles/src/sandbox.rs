@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::brain::{Brain, Innovations};
+use crate::config::SimConfig;
+use crate::sim_rng::SimRng;
+use crate::world::SpawnLayout;
+use crate::{spawn_bobble, spawn_plant, Bobble, BobbleGender, BobbleTraits, Plant};
+
+/// Keys for poking at a running ecosystem: J spawns a fresh random bobble
+/// where bobbles usually appear, F plants food under the cursor, and Delete
+/// clears every plant away.
+pub fn sandbox_controls(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<SimConfig>,
+    layout: Res<SpawnLayout>,
+    mut innovations: ResMut<Innovations>,
+    mut rng: ResMut<SimRng>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera2d>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    plants: Query<Entity, With<Plant>>,
+) {
+    let rng = &mut rng.0;
+
+    if kb_input.just_pressed(KeyCode::KeyJ) {
+        spawn_bobble(
+            &mut commands,
+            &asset_server,
+            &config,
+            layout.bobbles.sample(rng),
+            Bobble::new(BobbleGender::random(config.male_ratio, rng), 0, BobbleTraits::random(&config, rng)),
+            Brain::random(&mut innovations, rng),
+        );
+    }
+
+    if kb_input.just_pressed(KeyCode::KeyF) {
+        let (camera, camera_transform) = *camera;
+        // Nothing to aim at while the cursor is outside the window
+        if let Some(position) = window.cursor_position()
+            .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+        {
+            spawn_plant(&mut commands, &asset_server, &config, position, rng);
+        }
+    }
+
+    if kb_input.just_pressed(KeyCode::Delete) {
+        for plant in &plants {
+            commands.entity(plant).despawn();
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::config::SimConfig;
+
+/// The one source of randomness for spawning, respawning, infection, mutation
+/// and breeding, so a run started from the same `seed` plays out the same.
+/// Without a seed in the config a random one is picked, and printed so an
+/// interesting run can be repeated.
+#[derive(Resource)]
+pub struct SimRng(pub StdRng);
+
+impl FromWorld for SimRng {
+    fn from_world(world: &mut World) -> Self {
+        let seed = world.resource::<SimConfig>().seed.unwrap_or_else(|| rand::rng().random());
+        println!("Seed: {seed}");
+        Self(StdRng::seed_from_u64(seed))
+    }
+}
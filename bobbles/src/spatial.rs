@@ -0,0 +1,205 @@
+use bevy::prelude::*;
+
+use crate::corpse::Corpse;
+use crate::obstacle::Obstacle;
+use crate::thirst::Water;
+use crate::world::{k_nearest_in_radius, nearest_in_radius};
+use crate::{Bobble, Target, WORLD_SIZE};
+
+/// Side of a `SpatialIndex` cell. Around the radius most lookups use, so one
+/// only has to look a cell or two either way.
+const INDEX_CELL_SIZE: f32 = 100.;
+
+/// Items bucketed by where they are into square cells tiling the world, so a
+/// lookup near somewhere only has to check the cells around it. Anything off
+/// the world plane goes in the nearest edge cell.
+pub struct SpatialGrid<T> {
+    /// Cells across the world, and how wide each one is.
+    columns: i32,
+    cell_size: f32,
+    cells: Vec<Vec<(T, Vec2)>>,
+}
+
+impl<T: Copy> SpatialGrid<T> {
+    /// An empty grid with cells about `cell_size` across, trimmed so they
+    /// tile the world exactly.
+    pub fn new(cell_size: f32) -> Self {
+        let columns = (WORLD_SIZE / cell_size.max(1.)).ceil().max(1.) as i32;
+        Self {
+            columns,
+            cell_size: WORLD_SIZE / columns as f32,
+            cells: (0..columns * columns).map(|_| Vec::new()).collect(),
+        }
+    }
+
+    pub fn insert(&mut self, item: T, at: Vec2) {
+        let (column, row) = self.cell_of(at);
+        let index = self.index(column, row);
+        self.cells[index].push((item, at));
+    }
+
+    /// Empties the grid and fills it with `items`, keeping the allocations.
+    pub fn rebuild(&mut self, items: impl IntoIterator<Item = (T, Vec2)>) {
+        self.cells.iter_mut().for_each(Vec::clear);
+        for (item, at) in items {
+            self.insert(item, at);
+        }
+    }
+
+    /// Everything in the cells within `radius` of `position`: all of what's
+    /// within `radius`, and some of what's a little further.
+    pub fn near(&self, position: Vec2, radius: f32, wrap: bool) -> impl Iterator<Item = (T, Vec2)> + '_ {
+        let (column, row) = self.cell_of(position);
+        let columns = self.span(column, radius, wrap);
+        let rows = self.span(row, radius, wrap);
+        columns.flat_map(move |column| rows.clone().map(move |row| (column, row)))
+            .flat_map(move |(column, row)| self.cells[self.index(column, row)].iter().copied())
+    }
+
+    /// `nearest_in_radius` over the grid, looking further out a ring of cells
+    /// at a time until something turns up.
+    pub fn nearest(&self, position: Vec2, radius: f32, wrap: bool, mut accept: impl FnMut(&T) -> bool) -> Option<(T, Vec2)> {
+        let mut reach = self.cell_size;
+        loop {
+            let covers_all = self.covers_all(reach);
+            let reach_now = if covers_all { radius } else { reach.min(radius) };
+            let found = nearest_in_radius(position, reach_now, wrap, self.near(position, reach_now, wrap), &mut accept);
+            if found.is_some() || covers_all || reach_now >= radius {
+                return found;
+            }
+            reach *= 2.;
+        }
+    }
+
+    /// `k_nearest_in_radius` over the grid.
+    pub fn k_nearest(&self, position: Vec2, radius: f32, wrap: bool, k: usize, accept: impl FnMut(&T) -> bool) -> Vec<(T, Vec2)> {
+        k_nearest_in_radius(position, radius, wrap, k, self.near(position, radius, wrap), accept)
+    }
+
+    /// Whether cells `reach` either way of any one are all of them.
+    fn covers_all(&self, reach: f32) -> bool {
+        !reach.is_finite() || 2 * (reach / self.cell_size).ceil() as i32 + 1 >= self.columns
+    }
+
+    /// Columns (or rows) within `reach` of `center`, each once.
+    fn span(&self, center: i32, reach: f32, wrap: bool) -> std::ops::Range<i32> {
+        if self.covers_all(reach) {
+            return 0..self.columns;
+        }
+        let cells = (reach / self.cell_size).ceil() as i32;
+        if wrap {
+            // Mapped back onto the grid by `index`
+            center - cells..center + cells + 1
+        } else {
+            (center - cells).max(0)..(center + cells + 1).min(self.columns)
+        }
+    }
+
+    fn cell_of(&self, at: Vec2) -> (i32, i32) {
+        let cell = ((at + WORLD_SIZE / 2.) / self.cell_size).floor();
+        let last = self.columns - 1;
+        ((cell.x as i32).clamp(0, last), (cell.y as i32).clamp(0, last))
+    }
+
+    fn index(&self, column: i32, row: i32) -> usize {
+        (column.rem_euclid(self.columns) * self.columns + row.rem_euclid(self.columns)) as usize
+    }
+}
+
+/// Where everything bobbles look for is, as of the start of each step, so
+/// the systems that go looking only check what's close by.
+#[derive(Resource)]
+pub struct SpatialIndex {
+    pub bobbles: SpatialGrid<Entity>,
+    pub corpses: SpatialGrid<Entity>,
+    pub water: SpatialGrid<Entity>,
+    /// Each obstacle's radius.
+    pub obstacles: SpatialGrid<f32>,
+    /// The largest of those radii.
+    pub widest_obstacle: f32,
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        Self {
+            bobbles: SpatialGrid::new(INDEX_CELL_SIZE),
+            corpses: SpatialGrid::new(INDEX_CELL_SIZE),
+            water: SpatialGrid::new(INDEX_CELL_SIZE),
+            obstacles: SpatialGrid::new(INDEX_CELL_SIZE),
+            widest_obstacle: 0.,
+        }
+    }
+}
+
+fn position(transform: &Transform) -> Vec2 {
+    transform.translation.truncate()
+}
+
+pub fn rebuild_spatial_index(
+    mut index: ResMut<SpatialIndex>,
+    bobbles: Query<(Entity, &Transform), (With<Bobble>, Without<Target>)>,
+    corpses: Query<(Entity, &Transform), (With<Corpse>, Without<Bobble>)>,
+    water: Query<(Entity, &Transform), (With<Water>, Without<Bobble>)>,
+    obstacles: Query<(&Transform, &Obstacle), Without<Bobble>>,
+) {
+    index.bobbles.rebuild(bobbles.iter().map(|(entity, transform)| (entity, position(transform))));
+    index.corpses.rebuild(corpses.iter().map(|(entity, transform)| (entity, position(transform))));
+    index.water.rebuild(water.iter().map(|(entity, transform)| (entity, position(transform))));
+    index.obstacles.rebuild(obstacles.iter().map(|(transform, obstacle)| (obstacle.radius, position(transform))));
+    index.widest_obstacle = obstacles.iter().map(|(_, obstacle)| obstacle.radius).fold(0., f32::max);
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    /// `count` points spread over the world and a little past its edges.
+    fn scattered(count: usize, rng: &mut impl Rng) -> Vec<(usize, Vec2)> {
+        let reach = WORLD_SIZE / 2. + 20.;
+        (0..count).map(|i| (i, Vec2::new(rng.random_range(-reach..reach), rng.random_range(-reach..reach)))).collect()
+    }
+
+    #[test]
+    fn lookups_match_brute_force() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for cell_size in [30., 100., 2000.] {
+            let points = scattered(300, &mut rng);
+            let mut grid = SpatialGrid::new(cell_size);
+            grid.rebuild(points.iter().copied());
+            for _ in 0..200 {
+                let position = scattered(1, &mut rng)[0].1;
+                let radius = [10., 75., 400., f32::INFINITY][rng.random_range(0..4)];
+                let wrap = rng.random_bool(0.5);
+                let even = |i: &usize| i.is_multiple_of(2);
+
+                let nearest = grid.nearest(position, radius, wrap, even).map(|(i, _)| i);
+                let expected = nearest_in_radius(position, radius, wrap, points.iter().copied(), even).map(|(i, _)| i);
+                assert_eq!(nearest, expected, "cell {cell_size}, radius {radius}, wrap {wrap}");
+
+                let k_nearest: Vec<usize> = grid.k_nearest(position, radius, wrap, 5, even).into_iter().map(|(i, _)| i).collect();
+                let expected: Vec<usize> = k_nearest_in_radius(position, radius, wrap, 5, points.iter().copied(), even)
+                    .into_iter()
+                    .map(|(i, _)| i)
+                    .collect();
+                assert_eq!(k_nearest, expected, "cell {cell_size}, radius {radius}, wrap {wrap}");
+            }
+        }
+    }
+
+    #[test]
+    fn near_lists_each_item_once() {
+        let mut grid = SpatialGrid::new(100.);
+        grid.insert(0, Vec2::new(-490., 0.));
+        grid.insert(1, Vec2::new(490., 0.));
+        // Reaching most of the way round both ways still finds each just once
+        let mut found: Vec<usize> = grid.near(Vec2::ZERO, 450., true).map(|(i, _)| i).collect();
+        found.sort();
+        assert_eq!(found, vec![0, 1]);
+        // Across the seam only when wrapping
+        assert_eq!(grid.near(Vec2::new(-490., 0.), 50., true).count(), 2);
+        assert_eq!(grid.near(Vec2::new(-490., 0.), 50., false).count(), 1);
+    }
+}
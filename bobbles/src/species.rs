@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use neat::{Float, Species};
+
+use crate::brain::Brain;
+use crate::coloring::ColorMode;
+use crate::config::SimConfig;
+
+/// Weights of excess genes, disjoint genes and weight differences in the
+/// compatibility distance, as in the XOR runner.
+const EXCESS_COEFFICIENT: Float = 1.;
+const DISJOINT_COEFFICIENT: Float = 1.;
+const WEIGHT_COEFFICIENT: Float = 0.4;
+/// Species listed in the legend, biggest first.
+const LEGEND_LINES: usize = 8;
+
+/// Which species a bobble's brain was last sorted into, and its color.
+#[derive(Component)]
+pub struct SpeciesColor {
+    pub id: usize,
+    pub color: Color,
+}
+
+/// The living population split into species by brain structure, as NEAT
+/// does between generations. Redone every `speciation_interval` seconds.
+/// `members` index into the bobbles in the order that pass went through them.
+#[derive(Resource, Default)]
+pub struct BobbleSpecies {
+    pub species: Vec<Species>,
+    next_id: usize,
+    since_update: f32,
+}
+
+/// A color for species `id` that stays the same for as long as it lives.
+/// Stepping round the hue wheel by the golden angle keeps consecutive ids
+/// far apart.
+pub fn species_color(id: usize) -> Color {
+    Color::hsl((id as f32 * 137.508).rem_euclid(360.), 0.8, 0.55)
+}
+
+/// Sorts every brain into the first species whose representative is within
+/// `species_threshold`, founding a new one otherwise. Each surviving species
+/// then takes the first of its members as next time's representative, and
+/// the empty ones die out.
+pub fn assign_species(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<SimConfig>,
+    mut state: ResMut<BobbleSpecies>,
+    bobbles: Query<(Entity, &Brain)>,
+) {
+    // Counting time alone isn't a change the legend needs to hear about
+    let since_update = &mut state.bypass_change_detection().since_update;
+    *since_update += time.delta_secs();
+    if *since_update < config.speciation_interval {
+        return;
+    }
+    *since_update = 0.;
+
+    let BobbleSpecies { species, next_id, .. } = &mut *state;
+    for s in species.iter_mut() {
+        s.members.clear();
+    }
+    let brains: Vec<(Entity, &Brain)> = bobbles.iter().collect();
+    for (i, (entity, brain)) in brains.iter().enumerate() {
+        let existing = species.iter().position(|s| {
+            s.representative.compatibility_distance(&brain.genome, EXCESS_COEFFICIENT, DISJOINT_COEFFICIENT, WEIGHT_COEFFICIENT)
+                < config.species_threshold
+        });
+        let index = existing.unwrap_or_else(|| {
+            species.push(Species::new(*next_id, brain.genome.clone()));
+            *next_id += 1;
+            species.len() - 1
+        });
+        let s = &mut species[index];
+        s.members.push(i);
+        commands.entity(*entity).insert(SpeciesColor {
+            id: s.id,
+            color: species_color(s.id),
+        });
+    }
+
+    species.retain(|s| !s.members.is_empty());
+    for s in species.iter_mut() {
+        s.representative = brains[s.members[0]].1.genome.clone();
+    }
+}
+
+/// Marks the text listing the biggest species in their colors.
+#[derive(Component)]
+pub struct SpeciesLegend;
+
+/// The legend sits on the left, under the minimap.
+pub fn setup_species_legend(mut commands: Commands) {
+    commands.spawn((
+        SpeciesLegend,
+        Text::new("Species\n"),
+        TextFont {
+            font_size: 14.,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(204),
+            left: px(12),
+            ..default()
+        },
+        Visibility::Hidden,
+    ));
+}
+
+/// Shows the legend only while bobbles are colored by species, and rewrites
+/// it whenever the species are redone.
+pub fn update_species_legend(
+    mut commands: Commands,
+    mode: Res<ColorMode>,
+    state: Res<BobbleSpecies>,
+    legend: Single<(Entity, &mut Visibility), With<SpeciesLegend>>,
+) {
+    let (legend, mut visibility) = legend.into_inner();
+    visibility.set_if_neq(if *mode == ColorMode::Species { Visibility::Inherited } else { Visibility::Hidden });
+    if !state.is_changed() {
+        return;
+    }
+
+    let mut biggest: Vec<&Species> = state.species.iter().collect();
+    biggest.sort_by_key(|s| std::cmp::Reverse(s.members.len()));
+    commands.entity(legend).despawn_related::<Children>().with_children(|lines| {
+        for s in biggest.iter().take(LEGEND_LINES) {
+            lines.spawn((
+                TextSpan::new(format!("#{}: {} bobbles\n", s.id, s.members.len())),
+                TextColor(species_color(s.id)),
+            ));
+        }
+        if biggest.len() > LEGEND_LINES {
+            lines.spawn(TextSpan::new(format!("and {} more\n", biggest.len() - LEGEND_LINES)));
+        }
+    });
+}
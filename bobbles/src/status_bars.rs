@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+
+use crate::{Health, Hunger, PLAYER_SCALE};
+
+const BAR_WIDTH: f32 = 40.;
+const BAR_HEIGHT: f32 = 4.;
+const BAR_SPACING: f32 = 6.;
+/// Where the bars sit relative to their bobble, before any rotation.
+const BARS_OFFSET: Vec3 = Vec3::new(0., PLAYER_SCALE / 2. + BAR_SPACING, 0.1);
+const BAR_BACKGROUND: Color = Color::srgba(0., 0., 0., 0.6);
+const HEALTH_COLOR: Color = Color::srgb(0.9, 0.1, 0.1);
+const HUNGER_COLOR: Color = Color::srgb(0.9, 0.6, 0.1);
+
+/// Holds a bobble's bars so they can be shown or hidden together.
+#[derive(Component)]
+pub struct StatusBars;
+
+/// The filled part of a bar, scaled to the stat it tracks.
+#[derive(Component)]
+pub enum StatusBar {
+    Health,
+    Hunger,
+}
+
+/// Whether status bars are drawn. Toggled with B.
+#[derive(Resource)]
+pub struct StatusBarsVisible(pub bool);
+
+impl Default for StatusBarsVisible {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// Health and hunger bars to spawn as children of a bobble. Being children,
+/// they despawn along with it.
+pub fn status_bars() -> impl Bundle {
+    children![(
+        StatusBars,
+        Transform::from_translation(BARS_OFFSET),
+        Visibility::default(),
+        children![
+            bar(BAR_BACKGROUND, 0., 0.),
+            (StatusBar::Health, bar(HEALTH_COLOR, 0., 0.01)),
+            bar(BAR_BACKGROUND, -BAR_SPACING, 0.),
+            (StatusBar::Hunger, bar(HUNGER_COLOR, -BAR_SPACING, 0.01)),
+        ],
+    )]
+}
+
+fn bar(color: Color, y: f32, z: f32) -> impl Bundle {
+    (
+        Sprite::from_color(color, Vec2::new(BAR_WIDTH, BAR_HEIGHT)),
+        Transform::from_xyz(0., y, z),
+        Pickable::IGNORE,
+    )
+}
+
+pub fn update_status_bars(
+    bobbles: Query<(&Health, &Hunger)>,
+    containers: Query<&ChildOf, With<StatusBars>>,
+    mut bars: Query<(&StatusBar, &ChildOf, &mut Sprite, &mut Transform)>,
+) {
+    bars.iter_mut().for_each(|(bar, bar_parent, mut sprite, mut transform)| {
+        let Ok(container_parent) = containers.get(bar_parent.parent()) else { return };
+        let Ok((health, hunger)) = bobbles.get(container_parent.parent()) else { return };
+
+        let fraction = match bar {
+            StatusBar::Health => health.health / health.max_health,
+            StatusBar::Hunger => hunger.hunger / hunger.max_hunger,
+        }.clamp(0., 1.);
+
+        // Shrink towards the left edge
+        let width = BAR_WIDTH * fraction;
+        sprite.custom_size = Some(Vec2::new(width, BAR_HEIGHT));
+        transform.translation.x = -(BAR_WIDTH - width) / 2.;
+    });
+}
+
+/// Undoes the bobble's rotation so its bars stay level and above it whichever
+/// way it faces.
+pub fn level_status_bars(
+    bobbles: Query<&Transform, Without<StatusBars>>,
+    mut containers: Query<(&ChildOf, &mut Transform), With<StatusBars>>,
+) {
+    containers.iter_mut().for_each(|(parent, mut transform)| {
+        let Ok(bobble) = bobbles.get(parent.parent()) else { return };
+        let unrotate = bobble.rotation.inverse();
+        transform.rotation = unrotate;
+        transform.translation = unrotate * BARS_OFFSET;
+    });
+}
+
+pub fn toggle_status_bars(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut visible: ResMut<StatusBarsVisible>,
+    mut containers: Query<&mut Visibility, With<StatusBars>>,
+) {
+    if kb_input.just_pressed(KeyCode::KeyB) {
+        visible.0 = !visible.0;
+    }
+    // Also catches bars spawned after the last toggle
+    let wanted = if visible.0 { Visibility::Inherited } else { Visibility::Hidden };
+    for mut visibility in containers.iter_mut() {
+        visibility.set_if_neq(wanted);
+    }
+}
@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+
+use crate::config::SimConfig;
+use crate::sim_rng::SimRng;
+use crate::world::{wrapped_distance, SpawnLayout, WorldWrap};
+use crate::{Bobble, Health};
+
+/// Turns on thirst, water sources and the brain's water senses. Off runs the
+/// food-only model.
+pub const THIRST_ENABLED: bool = false;
+
+const MAX_THIRST: f32 = 100.;
+/// How much thirst decreases every second.
+const THIRST_RATE: f32 = 1.5;
+/// Health lost per second while thirst is at zero.
+const DEHYDRATION_DAMAGE: f32 = 1.5;
+/// Thirst restored per second spent in water.
+const DRINK_RATE: f32 = 40.;
+const WATER_SCALE: f32 = 80.;
+const WATER_COLOR: Color = Color::srgba(0.1, 0.4, 0.9, 0.8);
+
+#[derive(Component)]
+pub struct Thirst {
+    pub max_thirst: f32,
+    pub thirst: f32,
+}
+
+impl Thirst {
+    pub fn full() -> Self {
+        Self {
+            max_thirst: MAX_THIRST,
+            thirst: MAX_THIRST,
+        }
+    }
+}
+
+/// A pond bobbles drink from by standing in it. Never runs dry.
+#[derive(Component)]
+pub struct Water;
+
+pub fn spawn_water(mut commands: Commands, config: Res<SimConfig>, layout: Res<SpawnLayout>, mut rng: ResMut<SimRng>) {
+    let rng = &mut rng.0;
+    for _ in 0..config.water_count {
+        commands.spawn((
+            Water,
+            Sprite::from_color(WATER_COLOR, Vec2::splat(WATER_SCALE)),
+            // Just above the world plane so bobbles and plants draw over it
+            Transform::from_translation(layout.plants.sample(rng).extend(-0.5)),
+        ));
+    }
+}
+
+pub fn update_thirst(
+    time: Res<Time>,
+    mut bobbles: Query<(&mut Thirst, &mut Health)>,
+) {
+    bobbles.iter_mut().for_each(|(mut thirst, mut health)| {
+        thirst.thirst = (thirst.thirst - THIRST_RATE * time.delta_secs()).max(0.);
+
+        if thirst.thirst <= 0. {
+            health.health -= DEHYDRATION_DAMAGE * time.delta_secs();
+            health.timer.reset();
+        }
+    });
+}
+
+pub fn bobble_drinking_collision(
+    time: Res<Time>,
+    wrap: Res<WorldWrap>,
+    water: Query<&Transform, (With<Water>, Without<Bobble>)>,
+    mut bobbles: Query<(&Transform, &mut Thirst), With<Bobble>>,
+) {
+    bobbles.iter_mut().for_each(|(transform, mut thirst)| {
+        let position = transform.translation.truncate();
+        let in_water = water.iter()
+            .any(|pond| wrapped_distance(position, pond.translation.truncate(), wrap.0) < WATER_SCALE / 2.);
+        if in_water {
+            thirst.thirst = (thirst.thirst + DRINK_RATE * time.delta_secs()).min(thirst.max_thirst);
+        }
+    });
+}
@@ -0,0 +1,77 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::coloring::BaseColor;
+use crate::config::SimConfig;
+use crate::{Bobble, TELEPORT_DISTANCE};
+
+/// Whether trails are drawn behind bobbles. Toggled with T.
+#[derive(Resource)]
+pub struct Trails {
+    pub visible: bool,
+}
+
+impl Default for Trails {
+    fn default() -> Self {
+        Self { visible: true }
+    }
+}
+
+/// A bobble's most recent positions, newest at the back, at most
+/// `trail_length` of them. Goes when the bobble does.
+#[derive(Component, Default)]
+pub struct Trail {
+    points: VecDeque<Vec2>,
+}
+
+pub fn toggle_trails(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut trails: ResMut<Trails>,
+) {
+    if kb_input.just_pressed(KeyCode::KeyT) {
+        trails.visible = !trails.visible;
+    }
+}
+
+/// Adds this frame's position to every bobble's trail, giving bobbles one
+/// the first time round.
+pub fn record_trails(
+    mut commands: Commands,
+    config: Res<SimConfig>,
+    mut bobbles: Query<(Entity, &Transform, Option<&mut Trail>), With<Bobble>>,
+) {
+    for (entity, transform, trail) in bobbles.iter_mut() {
+        let Some(mut trail) = trail else {
+            commands.entity(entity).insert(Trail::default());
+            continue;
+        };
+        trail.points.push_back(transform.translation.truncate());
+        while trail.points.len() > config.trail_length {
+            trail.points.pop_front();
+        }
+    }
+}
+
+/// Draws each trail in its bobble's color, fading out towards the oldest
+/// point. Jumps across a wrapped edge are left out.
+pub fn draw_trails(
+    mut gizmos: Gizmos,
+    settings: Res<Trails>,
+    trails: Query<(&Trail, &BaseColor)>,
+) {
+    if !settings.visible {
+        return;
+    }
+    for (trail, color) in &trails {
+        let count = trail.points.len();
+        let segments = trail.points.iter().zip(trail.points.iter().skip(1));
+        for (i, (from, to)) in segments.enumerate() {
+            if from.distance(*to) > TELEPORT_DISTANCE {
+                continue;
+            }
+            let alpha = 0.6 * (i + 1) as f32 / count as f32;
+            gizmos.line_2d(*from, *to, color.0.with_alpha(alpha));
+        }
+    }
+}
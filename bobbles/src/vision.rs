@@ -0,0 +1,92 @@
+use bevy::prelude::*;
+
+use crate::world::wrapped_offset;
+
+/// Sectors the forward arc is split into. Part of the brain's input count, so
+/// changing it invalidates saved genomes.
+pub const VISION_BINS: usize = 5;
+/// Also give each sector a reading for the closest other bobble.
+pub const VISION_SEES_BOBBLES: bool = true;
+/// Brain inputs taken up by vision: one per sector for plants, one per sector
+/// for obstacles, and another per sector for bobbles when they're seen.
+pub const VISION_INPUTS: usize = VISION_BINS * if VISION_SEES_BOBBLES { 3 } else { 2 };
+
+/// The way a bobble is facing, which is the way it last steered. The vision
+/// cone is centred on it.
+#[derive(Component)]
+pub struct Heading(pub Vec2);
+
+impl Default for Heading {
+    fn default() -> Self {
+        Self(Vec2::X)
+    }
+}
+
+/// What one bobble can see from where it stands this step.
+pub struct VisionCone {
+    pub position: Vec2,
+    pub heading: Vec2,
+    pub range: f32,
+    /// Full width of the cone in degrees.
+    pub fov: f32,
+    pub wrap: bool,
+}
+
+impl VisionCone {
+    /// Distance to the closest of `targets` in each sector of the cone, as a
+    /// fraction of `range`, left to right. Empty sectors read as 1 (far
+    /// away). Targets hidden behind one of `obstacles` (centre, radius) aren't
+    /// seen.
+    pub fn sense(&self, targets: impl Iterator<Item = Vec2>, obstacles: &[(Vec2, f32)]) -> [f32; VISION_BINS] {
+        let obstacle_offsets: Vec<(Vec2, f32)> = obstacles.iter()
+            .map(|(center, radius)| (wrapped_offset(self.position, *center, self.wrap), *radius))
+            .collect();
+
+        let mut bins = [1.0_f32; VISION_BINS];
+        for target in targets {
+            let offset = wrapped_offset(self.position, target, self.wrap);
+            let Some(sector) = self.sector(offset) else { continue };
+            if obstacle_offsets.iter().any(|(center, radius)| blocks(offset, *center, *radius)) {
+                continue;
+            }
+            bins[sector] = bins[sector].min(offset.length() / self.range);
+        }
+        bins
+    }
+
+    /// Distance to the nearest obstacle surface in each sector, by where its
+    /// centre is, as a fraction of `range`. Empty sectors read as 1.
+    pub fn sense_obstacles(&self, obstacles: &[(Vec2, f32)]) -> [f32; VISION_BINS] {
+        let mut bins = [1.0_f32; VISION_BINS];
+        for (center, radius) in obstacles {
+            let offset = wrapped_offset(self.position, *center, self.wrap);
+            let Some(sector) = self.sector(offset) else { continue };
+            let surface = (offset.length() - radius).max(0.);
+            bins[sector] = bins[sector].min(surface / self.range);
+        }
+        bins
+    }
+
+    /// Which sector `offset` falls in, if it's within the cone.
+    fn sector(&self, offset: Vec2) -> Option<usize> {
+        let distance = offset.length();
+        if distance > self.range || distance <= f32::EPSILON {
+            return None;
+        }
+        let half_fov = self.fov.to_radians() / 2.;
+        // Positive angles are anticlockwise, i.e. to the left
+        let angle = self.heading.angle_to(offset);
+        if angle.abs() > half_fov {
+            return None;
+        }
+        let sector = (((half_fov - angle) / (2. * half_fov)) * VISION_BINS as f32) as usize;
+        Some(sector.min(VISION_BINS - 1))
+    }
+}
+
+/// Whether the circle at `center` crosses the line of sight from the origin
+/// to `target`.
+fn blocks(target: Vec2, center: Vec2, radius: f32) -> bool {
+    let along = (center.dot(target) / target.length_squared()).clamp(0., 1.);
+    center.distance(target * along) < radius
+}
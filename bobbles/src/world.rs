@@ -0,0 +1,240 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::brain::Momentum;
+use crate::config::SimConfig;
+use crate::sim_rng::SimRng;
+use crate::spatial::SpatialGrid;
+use crate::{Bobble, Plant, WORLD_SIZE};
+
+/// Redraws `SpawnDistribution::sample_spaced` allows before giving up on
+/// keeping its distance.
+const SPAWN_RETRIES: usize = 20;
+
+/// What happens to a bobble that reaches the edge of the world. Set by the
+/// config's `world_edge`.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum WorldEdge {
+    /// Stop at the edge.
+    Clamp,
+    /// Bounce back in by however far it overshot, heading back the way it
+    /// came.
+    Reflect,
+}
+
+impl FromWorld for WorldEdge {
+    fn from_world(world: &mut World) -> Self {
+        world.resource::<SimConfig>().world_edge
+    }
+}
+
+/// Turns the world into a torus: leaving one edge re-enters from the opposite
+/// one. Takes over from `WorldEdge` while on. Set by the config's
+/// `world_wrap`.
+#[derive(Resource)]
+pub struct WorldWrap(pub bool);
+
+impl FromWorld for WorldWrap {
+    fn from_world(world: &mut World) -> Self {
+        Self(world.resource::<SimConfig>().world_wrap)
+    }
+}
+
+/// How spawn positions are spread over the world.
+#[derive(Debug, Clone, Default)]
+pub enum SpawnDistribution {
+    /// Anywhere on the plane with equal chance.
+    #[default]
+    Uniform,
+    /// Gaussian blobs around `centers`, each picked with equal chance, with
+    /// `spread` as the standard deviation.
+    Clustered { centers: Vec<Vec2>, spread: f32 },
+}
+
+impl SpawnDistribution {
+    /// `count` clusters at random places on the plane.
+    pub fn random_clusters(count: usize, spread: f32, rng: &mut impl Rng) -> Self {
+        let centers = (0..count).map(|_| uniform_position(rng)).collect();
+        SpawnDistribution::Clustered { centers, spread }
+    }
+
+    /// A position on the world plane drawn from this distribution.
+    pub fn sample(&self, rng: &mut impl Rng) -> Vec2 {
+        match self {
+            SpawnDistribution::Clustered { centers, spread } if !centers.is_empty() => {
+                let center = centers[rng.random_range(0..centers.len())];
+                // Box-Muller for a standard normal pair
+                let radius = (-2. * (1. - rng.random::<f32>()).ln()).sqrt();
+                let angle = TAU * rng.random::<f32>();
+                let position = center + Vec2::from_angle(angle) * radius * *spread;
+                position.clamp(Vec2::splat(-WORLD_SIZE / 2.), Vec2::splat(WORLD_SIZE / 2.))
+            }
+            _ => uniform_position(rng),
+        }
+    }
+
+    /// Like `sample`, but redrawn up to `SPAWN_RETRIES` times while it lands
+    /// within `min_spacing` of any of `placed`. Crowded layouts may still
+    /// overlap rather than never finish. The position is added to `placed`,
+    /// which is quickest with cells about `min_spacing` across.
+    pub fn sample_spaced(&self, placed: &mut SpatialGrid<()>, min_spacing: f32, rng: &mut impl Rng) -> Vec2 {
+        let mut position = self.sample(rng);
+        for _ in 0..SPAWN_RETRIES {
+            if placed.near(position, min_spacing, false).all(|(_, other)| other.distance(position) >= min_spacing) {
+                break;
+            }
+            position = self.sample(rng);
+        }
+        placed.insert((), position);
+        position
+    }
+}
+
+/// A `SpawnDistribution` as the config describes it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SpawnPattern {
+    /// Anywhere on the plane.
+    Uniform,
+    /// `count` clusters at random places, picked afresh each run.
+    RandomClusters { count: usize, spread: f32 },
+    /// Clusters around the given (x, y) centers.
+    Clusters { centers: Vec<(f32, f32)>, spread: f32 },
+}
+
+impl SpawnPattern {
+    pub fn distribution(&self, rng: &mut impl Rng) -> SpawnDistribution {
+        match self {
+            SpawnPattern::Uniform => SpawnDistribution::Uniform,
+            SpawnPattern::RandomClusters { count, spread } => SpawnDistribution::random_clusters(*count, *spread, rng),
+            SpawnPattern::Clusters { centers, spread } => SpawnDistribution::Clustered {
+                centers: centers.iter().map(|&(x, y)| Vec2::new(x, y)).collect(),
+                spread: *spread,
+            },
+        }
+    }
+}
+
+/// Where new bobbles and plants appear, so food-rich and food-poor regions
+/// can be set up separately from where the population starts. Built from the
+/// config's `bobble_spawn` and `plant_spawn` at startup.
+#[derive(Resource)]
+pub struct SpawnLayout {
+    pub bobbles: SpawnDistribution,
+    pub plants: SpawnDistribution,
+}
+
+impl FromWorld for SpawnLayout {
+    fn from_world(world: &mut World) -> Self {
+        let config = world.resource::<SimConfig>().clone();
+        let rng = &mut world.resource_mut::<SimRng>().0;
+        Self {
+            bobbles: config.bobble_spawn.distribution(rng),
+            plants: config.plant_spawn.distribution(rng),
+        }
+    }
+}
+
+fn uniform_position(rng: &mut impl Rng) -> Vec2 {
+    Vec2::new(
+        rng.random_range(-WORLD_SIZE / 2.0..=WORLD_SIZE / 2.0),
+        rng.random_range(-WORLD_SIZE / 2.0..=WORLD_SIZE / 2.0),
+    )
+}
+
+/// Offset from `from` to `to`. Under wrap this is the shortest way, which may
+/// cross the seam.
+pub fn wrapped_offset(from: Vec2, to: Vec2, wrap: bool) -> Vec2 {
+    let offset = to - from;
+    if wrap {
+        offset - WORLD_SIZE * (offset / WORLD_SIZE).round()
+    } else {
+        offset
+    }
+}
+
+pub fn wrapped_distance(a: Vec2, b: Vec2, wrap: bool) -> f32 {
+    wrapped_offset(a, b, wrap).length()
+}
+
+/// The closest of `candidates`, each an item and where it is, that's within
+/// `radius` of `position` and that `accept` lets through, with the offset to
+/// it. Pass `f32::INFINITY` for no limit.
+pub fn nearest_in_radius<T>(
+    position: Vec2,
+    radius: f32,
+    wrap: bool,
+    candidates: impl IntoIterator<Item = (T, Vec2)>,
+    mut accept: impl FnMut(&T) -> bool,
+) -> Option<(T, Vec2)> {
+    candidates.into_iter()
+        .filter(|(item, _)| accept(item))
+        .map(|(item, at)| (item, wrapped_offset(position, at, wrap)))
+        .filter(|(_, offset)| offset.length() <= radius)
+        .min_by(|a, b| a.1.length_squared().total_cmp(&b.1.length_squared()))
+}
+
+/// Like `nearest_in_radius`, but up to `k` of them, nearest first.
+pub fn k_nearest_in_radius<T>(
+    position: Vec2,
+    radius: f32,
+    wrap: bool,
+    k: usize,
+    candidates: impl IntoIterator<Item = (T, Vec2)>,
+    mut accept: impl FnMut(&T) -> bool,
+) -> Vec<(T, Vec2)> {
+    let mut found: Vec<(T, Vec2)> = candidates.into_iter()
+        .filter(|(item, _)| accept(item))
+        .map(|(item, at)| (item, wrapped_offset(position, at, wrap)))
+        .filter(|(_, offset)| offset.length() <= radius)
+        .collect();
+    found.sort_by(|a, b| a.1.length_squared().total_cmp(&b.1.length_squared()));
+    found.truncate(k);
+    found
+}
+
+/// Keeps bobbles on the world plane.
+pub fn enforce_world_edges(
+    edge: Res<WorldEdge>,
+    wrap: Res<WorldWrap>,
+    mut bobbles: Query<(&mut Transform, Option<&mut Momentum>), With<Bobble>>,
+) {
+    if wrap.0 {
+        return;
+    }
+    let half = WORLD_SIZE / 2.;
+    bobbles.iter_mut().for_each(|(mut transform, mut momentum)| {
+        let position = &mut transform.translation;
+        for (axis, component) in [(&mut position.x, 0), (&mut position.y, 1)] {
+            // Which way is back in, if it's gone over
+            let inward = if *axis > half { -1. } else if *axis < -half { 1. } else { 0. };
+            *axis = match *edge {
+                WorldEdge::Clamp => axis.clamp(-half, half),
+                WorldEdge::Reflect if *axis > half => (2. * half - *axis).max(-half),
+                WorldEdge::Reflect if *axis < -half => (-2. * half - *axis).min(half),
+                WorldEdge::Reflect => *axis,
+            };
+            // A bounce sends it back the way it came, or it'd just hit the edge again
+            if inward != 0. && *edge == WorldEdge::Reflect && let Some(momentum) = momentum.as_mut() {
+                momentum.0[component] = inward * momentum.0[component].abs();
+            }
+        }
+    });
+}
+
+pub fn wrap_world(
+    wrap: Res<WorldWrap>,
+    mut entities: Query<&mut Transform, Or<(With<Bobble>, With<Plant>)>>,
+) {
+    if !wrap.0 {
+        return;
+    }
+    let half = WORLD_SIZE / 2.;
+    entities.iter_mut().for_each(|mut transform| {
+        let position = &mut transform.translation;
+        position.x = (position.x + half).rem_euclid(WORLD_SIZE) - half;
+        position.y = (position.y + half).rem_euclid(WORLD_SIZE) - half;
+    });
+}
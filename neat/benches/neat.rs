@@ -0,0 +1,102 @@
+//! Timing baselines for the hot paths: `cargo bench` in `neat/`. Fixtures
+//! are grown from fixed seeds, so every run times the same genomes.
+
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use neat::{Connectivity, Dataset, FitnessFn, Genome, InnovationHistory, MutationRates, Neat, NeatConfig, WeightInit};
+
+/// A genome with `inputs` x `outputs` minimal wiring grown by `growth` rounds
+/// of add-node and add-connection, standing in for small, medium and large
+/// evolved brains.
+fn fixture(inputs: usize, outputs: usize, growth: usize) -> Genome {
+    let mut history = InnovationHistory::default();
+    let mut rng = StdRng::seed_from_u64(growth as u64);
+    let mut genome = Genome::minimal(inputs, outputs, &mut history, &mut rng);
+    for _ in 0..growth {
+        genome.mutate_add_node(&mut history, &mut rng);
+        for _ in 0..3 {
+            genome.mutate_add_connection(&mut history, false, WeightInit::default(), &mut rng);
+        }
+    }
+    genome
+}
+
+fn config(population_size: usize) -> NeatConfig {
+    let xor = Dataset::xor();
+    NeatConfig {
+        population_size,
+        input_count: xor.input_count(),
+        output_count: xor.output_count(),
+        mutation_rates: MutationRates::default(),
+        weight_cap: 8.0,
+        allow_recurrent: false,
+        bias: true,
+        hall_of_fame_size: 10,
+        target_fitness: None,
+        fitness_history_cap: None,
+        compatibility_threshold: 3.0,
+        excess_coefficient: 1.0,
+        disjoint_coefficient: 1.0,
+        weight_coefficient: 0.4,
+        max_stagnation: 15,
+        target_species: None,
+        crossover_chance: 0.75,
+        interspecies_mating_rate: 0.01,
+        complexity_penalty: 0.0,
+        initial_connectivity: Connectivity::Full,
+        hidden_seeds: 0,
+        weight_init: WeightInit::default(),
+        champion_species_size: Some(5),
+        disabled_gene_chance: 0.75,
+        rank_selection: false,
+        seed: Some(0),
+    }
+}
+
+fn fixtures() -> [(&'static str, Genome); 3] {
+    [
+        ("small", fixture(6, 2, 0)),
+        ("medium", fixture(13, 2, 20)),
+        ("large", fixture(13, 2, 200)),
+    ]
+}
+
+fn compile(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compile");
+    for (name, genome) in fixtures() {
+        group.bench_function(name, |b| b.iter(|| genome.compile()));
+    }
+    group.finish();
+}
+
+fn activate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("activate");
+    for (name, genome) in fixtures() {
+        let mut network = genome.compile();
+        let inputs = vec![0.5; network.inputs_count()];
+        group.bench_function(name, |b| b.iter(|| network.activate(black_box(&inputs))));
+    }
+    group.finish();
+}
+
+fn evolve(c: &mut Criterion) {
+    let xor = Dataset::xor();
+    let mut group = c.benchmark_group("evolve/xor");
+    // One generation of a big population takes a while; fewer samples keep
+    // the suite quick
+    group.sample_size(10);
+    for population_size in [100, 1000] {
+        let mut neat = Neat::new(config(population_size));
+        group.bench_function(BenchmarkId::from_parameter(population_size), |b| {
+            b.iter(|| neat.evolve(|genome| xor.fitness(genome)));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, compile, activate, evolve);
+criterion_main!(benches);
@@ -0,0 +1,1240 @@
+use rand::Rng;
+use rand::prelude::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::Float;
+use crate::innovation::InnovationHistory;
+use crate::network::{NeuralNetwork, NodeState, OutputMode};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeType {
+    Input,
+    /// Always reads 1, so the nodes it feeds can shift their threshold.
+    Bias,
+    Hidden,
+    Output,
+}
+
+/// Squashing function of a hidden node. Each is applied to the node's
+/// weighted sum after `NeuralNetwork::steepness` has scaled it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Activation {
+    /// -1 to 1.
+    #[default]
+    Tanh,
+    /// 0 to 1.
+    Sigmoid,
+    /// 0 below zero, the sum itself above.
+    Relu,
+    /// The sum as is.
+    Identity,
+}
+
+impl Activation {
+    pub const ALL: [Activation; 4] = [Activation::Tanh, Activation::Sigmoid, Activation::Relu, Activation::Identity];
+
+    pub fn apply(self, x: Float) -> Float {
+        match self {
+            Activation::Tanh => x.tanh(),
+            Activation::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+            Activation::Relu => x.max(0.0),
+            Activation::Identity => x,
+        }
+    }
+
+    /// `apply` as a Rust expression on `x`, for `NeuralNetwork::to_rust`.
+    pub(crate) fn to_rust(self, x: &str) -> String {
+        match self {
+            Activation::Tanh => format!("({x}).tanh()"),
+            Activation::Sigmoid => format!("1.0 / (1.0 + (-({x})).exp())"),
+            Activation::Relu => format!("({x}).max(0.0)"),
+            Activation::Identity => format!("({x})"),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Activation::Tanh => "tanh",
+            Activation::Sigmoid => "sigmoid",
+            Activation::Relu => "relu",
+            Activation::Identity => "identity",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Connection {
+    pub from_idx: usize,
+    pub to_idx: usize,
+    pub weight: Float,
+    pub enabled: bool,
+    pub innovation: usize,
+    pub recurrent: bool, // Reads the source's value from the previous activation
+}
+
+impl Connection {
+    pub fn new(from_idx: usize, to_idx: usize, weight: Float, innovation: usize) -> Self {
+        Self {
+            from_idx,
+            to_idx,
+            weight,
+            enabled: true,
+            innovation,
+            recurrent: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Genome {
+    // Ordered by id so inputs always line up with the same input slots
+    pub nodes: BTreeMap<usize, NodeType>,
+    pub connections: Vec<Connection>,
+    // Hidden nodes that don't use tanh
+    pub activations: BTreeMap<usize, Activation>,
+    pub fitness: Float,
+}
+
+/// How many input-output connections a starting genome gets.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Connectivity {
+    /// Every input to every output.
+    #[default]
+    Full,
+    /// Each input-output pair independently, with this chance.
+    Sparse { probability: f64 },
+    /// None at all; structure has to grow through mutation.
+    None,
+}
+
+/// How the weights of new connections are drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeightInit {
+    /// Evenly from `[-range, range]`.
+    Uniform { range: Float },
+    /// From a normal distribution around zero.
+    Gaussian { std: Float },
+    /// From a normal distribution whose variance is one over the fan-in of
+    /// the node the connection leads into, so a node's summed input stays
+    /// about as spread out however many connections it has.
+    Xavier,
+}
+
+impl Default for WeightInit {
+    fn default() -> Self {
+        WeightInit::Uniform { range: 1.0 }
+    }
+}
+
+impl WeightInit {
+    /// A weight for a connection into a node with `fan_in` incoming
+    /// connections, the new one included.
+    pub fn sample(&self, fan_in: usize, rng: &mut impl Rng) -> Float {
+        match *self {
+            WeightInit::Uniform { range } => rng.random_range(-1.0..1.0 as Float) * range,
+            WeightInit::Gaussian { std } => standard_normal(rng) * std,
+            WeightInit::Xavier => standard_normal(rng) / (fan_in.max(1) as Float).sqrt(),
+        }
+    }
+}
+
+/// Box-Muller, keeping one of the pair.
+fn standard_normal(rng: &mut impl Rng) -> Float {
+    let radius = (-2.0 * (1.0 - rng.random::<Float>()).ln()).sqrt();
+    let angle = std::f64::consts::TAU as Float * rng.random::<Float>();
+    radius * angle.cos()
+}
+
+impl Genome {
+    /// Inputs `0..input_count` fully connected to outputs after them, with
+    /// weights drawn from `rng`. Every genome built this way shares node ids
+    /// and innovation numbers.
+    pub fn minimal(input_count: usize, output_count: usize, history: &mut InnovationHistory, rng: &mut impl Rng) -> Self {
+        Self::with_connectivity(input_count, output_count, Connectivity::Full, history, rng)
+    }
+
+    /// Like `minimal`, but with only the input-output connections
+    /// `connectivity` asks for. Unconnected outputs read as an activation of
+    /// zero until mutation wires them up.
+    pub fn with_connectivity(
+        input_count: usize,
+        output_count: usize,
+        connectivity: Connectivity,
+        history: &mut InnovationHistory,
+        rng: &mut impl Rng,
+    ) -> Self {
+        Self::with_hidden(input_count, output_count, connectivity, 0, WeightInit::default(), history, rng)
+    }
+
+    /// Like `with_connectivity`, plus a layer of `hidden_count` hidden nodes
+    /// with every input connected to each of them and each of them connected
+    /// to every output. The hidden nodes come right after the outputs, so
+    /// every genome built with the same counts shares them too. Weights are
+    /// drawn as `weight_init` says, and they and sparse connections from `rng`.
+    pub fn with_hidden(
+        input_count: usize,
+        output_count: usize,
+        connectivity: Connectivity,
+        hidden_count: usize,
+        weight_init: WeightInit,
+        history: &mut InnovationHistory,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let mut genome = Genome::default();
+        let outputs = input_count..input_count + output_count;
+        let hidden = outputs.end..outputs.end + hidden_count;
+        for id in 0..input_count {
+            genome.add_node(id, NodeType::Input);
+        }
+        for id in outputs.clone() {
+            genome.add_node(id, NodeType::Output);
+        }
+        for id in hidden.clone() {
+            genome.add_node(id, NodeType::Hidden);
+        }
+        // Fresh hidden nodes must get ids after the shared ones
+        history.next_node_id = history.next_node_id.max(genome.nodes.len());
+
+        for to in hidden.clone() {
+            for from in 0..input_count {
+                genome.add_connection(from, to, 0.0, history);
+            }
+        }
+        for from in hidden {
+            for to in outputs.clone() {
+                genome.add_connection(from, to, 0.0, history);
+            }
+        }
+
+        for from in 0..input_count {
+            for to in outputs.clone() {
+                let connected = match connectivity {
+                    Connectivity::Full => true,
+                    Connectivity::Sparse { probability } => rng.random_bool(probability.clamp(0.0, 1.0)),
+                    Connectivity::None => false,
+                };
+                if connected {
+                    genome.add_connection(from, to, 0.0, history);
+                }
+            }
+        }
+
+        // Weights come last, once every node's fan-in is known
+        let mut fan_in: HashMap<usize, usize> = HashMap::new();
+        for conn in &genome.connections {
+            *fan_in.entry(conn.to_idx).or_default() += 1;
+        }
+        for conn in &mut genome.connections {
+            conn.weight = weight_init.sample(fan_in[&conn.to_idx], rng);
+        }
+        genome
+    }
+
+    /// The same genome plus a bias node connected to every hidden and output
+    /// node, with weights drawn as `weight_init` says. It takes the id after
+    /// the highest one, so genomes built alike and given a bias before any
+    /// mutation all share it.
+    pub fn with_bias(mut self, weight_init: WeightInit, history: &mut InnovationHistory, rng: &mut impl Rng) -> Self {
+        let bias = self.nodes.keys().next_back().map_or(0, |id| id + 1);
+        let targets: Vec<usize> = self.nodes.iter()
+            .filter(|(_, t)| matches!(t, NodeType::Hidden | NodeType::Output))
+            .map(|(id, _)| *id)
+            .collect();
+        self.add_node(bias, NodeType::Bias);
+        history.next_node_id = history.next_node_id.max(bias + 1);
+        for to in targets {
+            let fan_in = self.connections.iter().filter(|c| c.enabled && c.to_idx == to).count() + 1;
+            let weight = weight_init.sample(fan_in, rng);
+            self.add_connection(bias, to, weight, history);
+        }
+        self
+    }
+
+    pub fn add_node(&mut self, id: usize, node_type: NodeType) {
+        self.nodes.insert(id, node_type);
+    }
+
+    pub fn add_connection(&mut self, from: usize, to: usize, weight: Float, history: &mut InnovationHistory) {
+        let innovation = history.get_innovation(from, to);
+        self.connections.push(Connection::new(from, to, weight, innovation));
+    }
+
+    /// What hidden node `id` squashes its sum with. Tanh unless mutation
+    /// changed it.
+    pub fn activation(&self, id: usize) -> Activation {
+        self.activations.get(&id).copied().unwrap_or_default()
+    }
+
+    /// The genome as a network ready to `activate`. Panics if a connection
+    /// refers to a node the genome doesn't have; when the genome didn't come
+    /// from this crate's own constructors and mutations, `try_compile`
+    /// reports that and anything else malformed as errors instead. Debug
+    /// builds also catch genomes without outputs here, before their empty
+    /// output lists trip something downstream.
+    pub fn compile(&self) -> NeuralNetwork {
+        debug_assert!(
+            self.nodes.values().any(|&t| t == NodeType::Output),
+            "compiling a genome with no output nodes, try_compile reports this",
+        );
+        let mut nodes_vec = Vec::new();
+        let mut id_to_idx = HashMap::new();
+
+        for (id, node_type) in &self.nodes {
+            id_to_idx.insert(*id, nodes_vec.len());
+            nodes_vec.push(NodeState {
+                id: *id,
+                value: if *node_type == NodeType::Bias { 1.0 } else { 0.0 },
+                activation: self.activation(*id),
+                incoming: Vec::new(),
+                recurrent: Vec::new(),
+                node_type: *node_type,
+            });
+        }
+
+        for conn in self.connections.iter().filter(|c| c.enabled) {
+            let idx = |node: usize| -> usize {
+                *id_to_idx.get(&node).unwrap_or_else(|| {
+                    panic!("connection {} refers to missing node {node}, try_compile reports this", conn.innovation)
+                })
+            };
+            let (to_idx, from_idx) = (idx(conn.to_idx), idx(conn.from_idx));
+            if conn.recurrent {
+                nodes_vec[to_idx].recurrent.push((from_idx, conn.weight));
+            } else {
+                nodes_vec[to_idx].incoming.push((from_idx, conn.weight));
+            }
+        }
+
+        let mut execution_order = Vec::new();
+        let mut visited = HashSet::new();
+
+        fn visit(
+            idx: usize,
+            nodes: &[NodeState],
+            visited: &mut HashSet<usize>,
+            order: &mut Vec<usize>,
+        ) {
+            if visited.contains(&idx) || matches!(nodes[idx].node_type, NodeType::Input | NodeType::Bias) { return; }
+            for (from_idx, _) in &nodes[idx].incoming {
+                visit(*from_idx, nodes, visited, order);
+            }
+            visited.insert(idx);
+            order.push(idx);
+        }
+
+        let output_indices: Vec<usize> = nodes_vec.iter().enumerate()
+            .filter(|(_, n)| n.node_type == NodeType::Output)
+            .map(|(i, _)| i).collect();
+
+        for &out_idx in &output_indices {
+            visit(out_idx, &nodes_vec, &mut visited, &mut execution_order);
+        }
+
+        // Nodes that only feed the outputs through recurrent edges still need
+        // computing. They can go last since their readers use last step's value.
+        let mut scheduled = 0;
+        while scheduled < execution_order.len() {
+            let idx = execution_order[scheduled];
+            let sources: Vec<usize> = nodes_vec[idx].recurrent.iter().map(|(from_idx, _)| *from_idx).collect();
+            for from_idx in sources {
+                visit(from_idx, &nodes_vec, &mut visited, &mut execution_order);
+            }
+            scheduled += 1;
+        }
+
+        NeuralNetwork {
+            nodes: nodes_vec,
+            execution_order,
+            inputs_count: self.nodes.values().filter(|&&t| t == NodeType::Input).count(),
+            output_indices,
+            has_recurrent: self.connections.iter().any(|c| c.enabled && c.recurrent),
+            output_mode: OutputMode::default(),
+            steepness: 1.0,
+        }
+    }
+
+    /// Whether adding a forward edge `from -> to` would close a loop, i.e. `to`
+    /// can already reach `from` through forward (non-recurrent) connections.
+    pub fn creates_cycle(&self, from: usize, to: usize) -> bool {
+        let mut stack = vec![to];
+        let mut seen = HashSet::new();
+        while let Some(node) = stack.pop() {
+            if node == from {
+                return true;
+            }
+            if seen.insert(node) {
+                stack.extend(self.connections.iter()
+                    .filter(|c| !c.recurrent && c.from_idx == node)
+                    .map(|c| c.to_idx));
+            }
+        }
+        false
+    }
+
+    /// NEAT compatibility distance `c1*E/N + c2*D/N + c3*W`: excess genes E
+    /// (innovations past the other genome's newest), disjoint genes D (the
+    /// rest of the unmatched ones) and the mean weight difference W of
+    /// matching genes. N is the larger gene count, or 1 for genomes under 20
+    /// genes so small genomes aren't pushed apart by a couple of mismatches.
+    pub fn compatibility_distance(&self, other: &Genome, c1: Float, c2: Float, c3: Float) -> Float {
+        // Ordered so the weight differences always sum the same way
+        let ours: BTreeMap<usize, Float> = self.connections.iter().map(|c| (c.innovation, c.weight)).collect();
+        let theirs: BTreeMap<usize, Float> = other.connections.iter().map(|c| (c.innovation, c.weight)).collect();
+        let our_max = ours.keys().copied().max().unwrap_or(0);
+        let their_max = theirs.keys().copied().max().unwrap_or(0);
+
+        let mut excess = 0;
+        let mut disjoint = 0;
+        let mut weight_diff = 0.0;
+        let mut matching = 0;
+        for (innovation, weight) in &ours {
+            match theirs.get(innovation) {
+                Some(other_weight) => {
+                    weight_diff += (weight - other_weight).abs();
+                    matching += 1;
+                }
+                None if *innovation > their_max => excess += 1,
+                None => disjoint += 1,
+            }
+        }
+        for innovation in theirs.keys().filter(|i| !ours.contains_key(i)) {
+            if *innovation > our_max { excess += 1 } else { disjoint += 1 }
+        }
+
+        let genes = ours.len().max(theirs.len());
+        let n = if genes < 20 { 1.0 } else { genes as Float };
+        let w = if matching > 0 { weight_diff / matching as Float } else { 0.0 };
+        c1 * excess as Float / n + c2 * disjoint as Float / n + c3 * w
+    }
+
+    /// Child of `self` and `other` by the NEAT rules. Matching genes come from
+    /// either parent at random, and if either parent has the gene disabled it
+    /// stays disabled with chance `disabled_chance`. Disjoint and excess genes
+    /// come from the fitter parent, or from both when their fitness is equal;
+    /// the weaker side's are then skipped where they'd close a loop.
+    pub fn crossover(&self, other: &Genome, disabled_chance: f64, rng: &mut impl Rng) -> Genome {
+        // Build on the fitter parent's topology
+        let (fitter, weaker) = if other.fitness > self.fitness { (other, self) } else { (self, other) };
+        let theirs: HashMap<usize, &Connection> = weaker.connections.iter().map(|c| (c.innovation, c)).collect();
+        let connections = fitter.connections.iter().map(|conn| {
+            match theirs.get(&conn.innovation) {
+                Some(other_conn) => {
+                    let mut gene = if rng.random_bool(0.5) {
+                        Connection {
+                            // Keep our cycle bookkeeping, it's our topology the gene lands in
+                            recurrent: conn.recurrent,
+                            ..(*other_conn).clone()
+                        }
+                    } else {
+                        conn.clone()
+                    };
+                    if !conn.enabled || !other_conn.enabled {
+                        gene.enabled = !rng.random_bool(disabled_chance);
+                    }
+                    gene
+                }
+                None => conn.clone(),
+            }
+        }).collect();
+
+        let mut child = Genome {
+            nodes: fitter.nodes.clone(),
+            connections,
+            activations: fitter.activations.clone(),
+            fitness: 0.0,
+        };
+        if self.fitness == other.fitness {
+            let ours: HashSet<usize> = child.connections.iter().map(|c| c.innovation).collect();
+            for conn in weaker.connections.iter().filter(|c| !ours.contains(&c.innovation)) {
+                if !conn.recurrent && child.creates_cycle(conn.from_idx, conn.to_idx) {
+                    continue;
+                }
+                for id in [conn.from_idx, conn.to_idx] {
+                    if let Some(&node_type) = weaker.nodes.get(&id)
+                        && !child.nodes.contains_key(&id)
+                    {
+                        child.nodes.insert(id, node_type);
+                        if let Some(&activation) = weaker.activations.get(&id) {
+                            child.activations.insert(id, activation);
+                        }
+                    }
+                }
+                child.connections.push(conn.clone());
+            }
+        }
+        child
+    }
+
+    /// Same nodes with the same activations and the same enabled connections
+    /// (by innovation number), whatever the weights.
+    pub fn structural_eq(&self, other: &Genome) -> bool {
+        let enabled = |genome: &Genome| -> HashSet<usize> {
+            genome.connections.iter().filter(|c| c.enabled).map(|c| c.innovation).collect()
+        };
+        self.nodes == other.nodes
+            && self.nodes.keys().all(|&id| self.activation(id) == other.activation(id))
+            && enabled(self) == enabled(other)
+    }
+
+    /// What changed going from this genome to `other`: nodes by id and
+    /// connections by innovation number that only one of them has, matching
+    /// connections that were switched on or off, and matching weights that
+    /// moved. Prints as one line per change.
+    pub fn diff(&self, other: &Genome) -> GenomeDiff {
+        let node_changes = |from: &Genome, to: &Genome| -> Vec<(usize, NodeType)> {
+            to.nodes.iter()
+                .filter(|(id, _)| !from.nodes.contains_key(id))
+                .map(|(id, node_type)| (*id, *node_type))
+                .collect()
+        };
+        let by_innovation = |genome: &Genome| -> BTreeMap<usize, Connection> {
+            genome.connections.iter().map(|c| (c.innovation, c.clone())).collect()
+        };
+        let before = by_innovation(self);
+        let after = by_innovation(other);
+
+        let mut diff = GenomeDiff {
+            added_nodes: node_changes(self, other),
+            removed_nodes: node_changes(other, self),
+            ..GenomeDiff::default()
+        };
+        for (innovation, conn) in &after {
+            let Some(old) = before.get(innovation) else {
+                diff.added_connections.push(conn.clone());
+                continue;
+            };
+            if old.enabled != conn.enabled {
+                diff.toggled_connections.push((*innovation, conn.enabled));
+            }
+            if old.weight != conn.weight {
+                diff.weight_changes.push((*innovation, old.weight, conn.weight));
+            }
+        }
+        diff.removed_connections = before.into_iter()
+            .filter(|(innovation, _)| !after.contains_key(innovation))
+            .map(|(_, conn)| conn)
+            .collect();
+        diff
+    }
+
+    /// Node count and enabled connection count.
+    pub fn complexity(&self) -> (usize, usize) {
+        (self.nodes.len(), self.connections.iter().filter(|c| c.enabled).count())
+    }
+
+    /// Graphviz digraph of the genome for inspecting evolved topologies, e.g.
+    /// `dot -Tpng genome.dot -o genome.png`. Nodes are colored by type, edges
+    /// labeled with their weight, disabled edges dashed and recurrent edges red.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph genome {\n    rankdir=LR;\n    node [style=filled];\n");
+        for (id, node_type) in &self.nodes {
+            let (shape, color) = match node_type {
+                NodeType::Input => ("box", "lightblue"),
+                NodeType::Bias => ("box", "khaki"),
+                NodeType::Hidden => ("circle", "lightgray"),
+                NodeType::Output => ("doublecircle", "palegreen"),
+            };
+            let label = match self.activations.get(id) {
+                Some(activation) => format!("{id} {}", activation.name()),
+                None => id.to_string(),
+            };
+            dot.push_str(&format!("    n{id} [label=\"{label}\", shape={shape}, fillcolor={color}];\n"));
+        }
+        for conn in &self.connections {
+            let style = if conn.enabled { "solid" } else { "dashed" };
+            // Keep recurrent edges out of the layout ranking so they draw as loops back
+            let extra = if conn.recurrent { ", color=red, constraint=false" } else { "" };
+            dot.push_str(&format!(
+                "    n{} -> n{} [label=\"{:.3}\", style={style}{extra}];\n",
+                conn.from_idx, conn.to_idx, conn.weight,
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Plain-text form of the genome, one item per line:
+    /// `fitness <f>`, `node <id> <type>`, followed by the activation for
+    /// hidden nodes that don't use tanh, and
+    /// `conn <from> <to> <weight> <enabled> <innovation> <recurrent>`.
+    /// Read back with `from_text`.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("fitness {}\n", self.fitness);
+        for (id, node_type) in &self.nodes {
+            let name = match node_type {
+                NodeType::Input => "input",
+                NodeType::Bias => "bias",
+                NodeType::Hidden => "hidden",
+                NodeType::Output => "output",
+            };
+            match self.activations.get(id) {
+                Some(activation) => text.push_str(&format!("node {id} {name} {}\n", activation.name())),
+                None => text.push_str(&format!("node {id} {name}\n")),
+            }
+        }
+        for conn in &self.connections {
+            text.push_str(&format!(
+                "conn {} {} {} {} {} {}\n",
+                conn.from_idx, conn.to_idx, conn.weight, conn.enabled, conn.innovation, conn.recurrent,
+            ));
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Genome, String> {
+        fn field<T: std::str::FromStr>(fields: &[&str], i: usize, line: &str) -> Result<T, String> {
+            fields.get(i)
+                .and_then(|f| f.parse().ok())
+                .ok_or_else(|| format!("malformed genome line: {line}"))
+        }
+
+        let mut genome = Genome::default();
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[0] {
+                "fitness" => genome.fitness = field(&fields, 1, line)?,
+                "node" => {
+                    let node_type = match fields.get(2) {
+                        Some(&"input") => NodeType::Input,
+                        Some(&"bias") => NodeType::Bias,
+                        Some(&"hidden") => NodeType::Hidden,
+                        Some(&"output") => NodeType::Output,
+                        _ => return Err(format!("malformed genome line: {line}")),
+                    };
+                    let id = field(&fields, 1, line)?;
+                    genome.add_node(id, node_type);
+                    if let Some(name) = fields.get(3) {
+                        let activation = Activation::from_name(name)
+                            .ok_or_else(|| format!("malformed genome line: {line}"))?;
+                        genome.activations.insert(id, activation);
+                    }
+                }
+                "conn" => genome.connections.push(Connection {
+                    from_idx: field(&fields, 1, line)?,
+                    to_idx: field(&fields, 2, line)?,
+                    weight: field(&fields, 3, line)?,
+                    enabled: field(&fields, 4, line)?,
+                    innovation: field(&fields, 5, line)?,
+                    recurrent: field(&fields, 6, line)?,
+                }),
+                _ => return Err(format!("unknown genome line: {line}")),
+            }
+        }
+        Ok(genome)
+    }
+
+    /// Checks the genome is well formed: it has an output node, every
+    /// connection joins existing nodes, nothing connects into an input or bias
+    /// node or out of an output, innovation numbers are unique and the forward
+    /// (non-recurrent) connections don't form a loop. Returns every problem
+    /// found.
+    pub fn validate(&self) -> Result<(), Vec<GenomeError>> {
+        let mut errors = Vec::new();
+        // Its network would activate to nothing, and callers read outputs by index
+        if !self.nodes.values().any(|&t| t == NodeType::Output) {
+            errors.push(GenomeError::NoOutputs);
+        }
+        let mut innovations = HashSet::new();
+        for conn in &self.connections {
+            for node in [conn.from_idx, conn.to_idx] {
+                if !self.nodes.contains_key(&node) {
+                    errors.push(GenomeError::MissingNode { innovation: conn.innovation, node });
+                }
+            }
+            if matches!(self.nodes.get(&conn.to_idx), Some(NodeType::Input | NodeType::Bias)) {
+                errors.push(GenomeError::IntoInput { innovation: conn.innovation, node: conn.to_idx });
+            }
+            if self.nodes.get(&conn.from_idx) == Some(&NodeType::Output) {
+                errors.push(GenomeError::FromOutput { innovation: conn.innovation, node: conn.from_idx });
+            }
+            if !innovations.insert(conn.innovation) {
+                errors.push(GenomeError::DuplicateInnovation(conn.innovation));
+            }
+        }
+
+        // Kahn's algorithm: anything left unscheduled sits on or behind a loop
+        let forward: Vec<&Connection> = self.connections.iter()
+            .filter(|c| c.enabled && !c.recurrent)
+            .collect();
+        let mut incoming: HashMap<usize, usize> = HashMap::new();
+        for conn in &forward {
+            *incoming.entry(conn.to_idx).or_default() += 1;
+        }
+        let mut ready: Vec<usize> = self.nodes.keys().copied().filter(|id| !incoming.contains_key(id)).collect();
+        let mut scheduled = 0;
+        while let Some(node) = ready.pop() {
+            scheduled += 1;
+            for conn in forward.iter().filter(|c| c.from_idx == node) {
+                let count = incoming.get_mut(&conn.to_idx).expect("counted above");
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(conn.to_idx);
+                }
+            }
+        }
+        if errors.is_empty() && scheduled < self.nodes.len() {
+            errors.push(GenomeError::Cycle);
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// `compile`, but validating first so a malformed genome is reported
+    /// instead of panicking partway through.
+    pub fn try_compile(&self) -> Result<NeuralNetwork, Vec<GenomeError>> {
+        self.validate()?;
+        Ok(self.compile())
+    }
+}
+
+/// Something `Genome::validate` found wrong with a genome.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenomeError {
+    /// There are no output nodes.
+    NoOutputs,
+    /// A connection refers to a node the genome doesn't have.
+    MissingNode { innovation: usize, node: usize },
+    /// A connection leads into an input or bias node.
+    IntoInput { innovation: usize, node: usize },
+    /// A connection leads out of an output node.
+    FromOutput { innovation: usize, node: usize },
+    /// More than one connection has this innovation number.
+    DuplicateInnovation(usize),
+    /// Forward connections loop back on themselves.
+    Cycle,
+}
+
+impl std::fmt::Display for GenomeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenomeError::NoOutputs => write!(f, "genome has no output nodes"),
+            GenomeError::MissingNode { innovation, node } => {
+                write!(f, "connection {innovation} refers to missing node {node}")
+            }
+            GenomeError::IntoInput { innovation, node } => {
+                write!(f, "connection {innovation} leads into input or bias node {node}")
+            }
+            GenomeError::FromOutput { innovation, node } => {
+                write!(f, "connection {innovation} leads out of output node {node}")
+            }
+            GenomeError::DuplicateInnovation(innovation) => {
+                write!(f, "innovation {innovation} is used by more than one connection")
+            }
+            GenomeError::Cycle => write!(f, "forward connections form a cycle"),
+        }
+    }
+}
+
+impl std::error::Error for GenomeError {}
+
+/// Everything that differs between two genomes, from `Genome::diff`. Each
+/// list is in order of node id or innovation number.
+#[derive(Debug, Clone, Default)]
+pub struct GenomeDiff {
+    pub added_nodes: Vec<(usize, NodeType)>,
+    pub removed_nodes: Vec<(usize, NodeType)>,
+    pub added_connections: Vec<Connection>,
+    pub removed_connections: Vec<Connection>,
+    /// Innovation number and whether the connection is now enabled.
+    pub toggled_connections: Vec<(usize, bool)>,
+    /// Innovation number, old weight and new weight.
+    pub weight_changes: Vec<(usize, Float, Float)>,
+}
+
+impl GenomeDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.added_connections.is_empty()
+            && self.removed_connections.is_empty()
+            && self.toggled_connections.is_empty()
+            && self.weight_changes.is_empty()
+    }
+}
+
+impl std::fmt::Display for GenomeDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no differences");
+        }
+        for (id, node_type) in &self.added_nodes {
+            writeln!(f, "+ node {id} ({node_type:?})")?;
+        }
+        for (id, node_type) in &self.removed_nodes {
+            writeln!(f, "- node {id} ({node_type:?})")?;
+        }
+        for conn in &self.added_connections {
+            writeln!(f, "+ connection {}: {} -> {} weight {:.3}", conn.innovation, conn.from_idx, conn.to_idx, conn.weight)?;
+        }
+        for conn in &self.removed_connections {
+            writeln!(f, "- connection {}: {} -> {} weight {:.3}", conn.innovation, conn.from_idx, conn.to_idx, conn.weight)?;
+        }
+        for (innovation, enabled) in &self.toggled_connections {
+            writeln!(f, "~ connection {innovation} {}", if *enabled { "enabled" } else { "disabled" })?;
+        }
+        for (innovation, old, new) in &self.weight_changes {
+            writeln!(f, "~ connection {innovation} weight {old:.3} -> {new:.3} ({:+.3})", new - old)?;
+        }
+        Ok(())
+    }
+}
+
+// --- 5. MUTATION LOGIC ---
+
+/// Chance of each mutation being the one `Genome::mutate` applies. They're
+/// exclusive, so they should add up to at most 1; whatever is left over is the
+/// chance of no mutation at all.
+#[derive(Debug, Clone, Copy)]
+pub struct MutationRates {
+    pub weight: f64,
+    pub add_connection: f64,
+    pub add_node: f64,
+    pub delete_connection: f64,
+    pub toggle_enable: f64,
+    pub activation: f64,
+}
+
+impl Default for MutationRates {
+    fn default() -> Self {
+        Self {
+            weight: 0.8,
+            add_connection: 0.05,
+            add_node: 0.03,
+            delete_connection: 0.0,
+            toggle_enable: 0.0,
+            activation: 0.0,
+        }
+    }
+}
+
+impl Genome {
+    /// Applies one mutation picked according to `rates`, then clamps weights
+    /// to `weight_cap`. `allow_recurrent` lets add-connection create recurrent
+    /// edges; without it the genome stays strictly feed-forward. New connections
+    /// get their weights from `WeightInit::default()`.
+    pub fn mutate(&mut self, history: &mut InnovationHistory, rates: &MutationRates, weight_cap: Float, allow_recurrent: bool) {
+        self.mutate_with_rng(history, rates, weight_cap, allow_recurrent, WeightInit::default(), &mut rand::rng());
+    }
+
+    /// `mutate`, with new connections' weights drawn as `weight_init` says
+    /// and everything drawn from `rng`, so a seeded one gives the same
+    /// mutation every time.
+    pub fn mutate_with_rng(
+        &mut self,
+        history: &mut InnovationHistory,
+        rates: &MutationRates,
+        weight_cap: Float,
+        allow_recurrent: bool,
+        weight_init: WeightInit,
+        rng: &mut impl Rng,
+    ) {
+        let mut roll: f64 = rng.random();
+        let mut picked = |rate: f64| {
+            let hit = roll < rate;
+            roll -= rate;
+            hit
+        };
+
+        if picked(rates.weight) {
+            self.mutate_weights(weight_cap, rng);
+        } else if picked(rates.add_connection) {
+            self.mutate_add_connection(history, allow_recurrent, weight_init, rng);
+        } else if picked(rates.add_node) {
+            self.mutate_add_node(history, rng);
+        } else if picked(rates.delete_connection) {
+            self.mutate_delete_connection(rng);
+        } else if picked(rates.toggle_enable) {
+            self.mutate_toggle_enable(rng);
+        } else if picked(rates.activation) {
+            self.mutate_activation(rng);
+        }
+
+        self.clamp_weights(weight_cap);
+    }
+
+    /// Removes a random connection. Returns whether there was one to remove.
+    pub fn mutate_delete_connection(&mut self, rng: &mut impl Rng) -> bool {
+        if self.connections.is_empty() {
+            return false;
+        }
+        let idx = rng.random_range(0..self.connections.len());
+        self.connections.remove(idx);
+        true
+    }
+
+    /// Flips a random connection between enabled and disabled. Returns whether
+    /// there was one to flip.
+    pub fn mutate_toggle_enable(&mut self, rng: &mut impl Rng) -> bool {
+        let Some(conn) = self.connections.iter_mut().choose(rng) else {
+            return false;
+        };
+        conn.enabled = !conn.enabled;
+        true
+    }
+
+    /// Gives a random hidden node a different activation. Returns whether
+    /// there was a hidden node to change.
+    pub fn mutate_activation(&mut self, rng: &mut impl Rng) -> bool {
+        let Some(id) = self.nodes.iter().filter(|(_, t)| **t == NodeType::Hidden).map(|(id, _)| *id).choose(rng) else {
+            return false;
+        };
+        let current = self.activation(id);
+        let activation = *Activation::ALL.iter().filter(|a| **a != current).choose(rng).expect("there's more than one");
+        if activation == Activation::Tanh {
+            self.activations.remove(&id);
+        } else {
+            self.activations.insert(id, activation);
+        }
+        true
+    }
+
+    /// Nudges most weights slightly and resets the rest to a fresh value.
+    pub fn mutate_weights(&mut self, weight_cap: Float, rng: &mut impl Rng) {
+        for conn in &mut self.connections {
+            if rng.random_bool(0.9) {
+                conn.weight += rng.random_range(-0.1..0.1); // Nudge
+            } else {
+                conn.weight = rng.random_range(-1.0..1.0 as Float).clamp(-weight_cap, weight_cap); // Reset
+            }
+        }
+    }
+
+    /// Tries to connect two random nodes, with a weight drawn as `weight_init`
+    /// says. Returns whether a connection was added.
+    pub fn mutate_add_connection(
+        &mut self,
+        history: &mut InnovationHistory,
+        allow_recurrent: bool,
+        weight_init: WeightInit,
+        rng: &mut impl Rng,
+    ) -> bool {
+        let keys: Vec<usize> = self.nodes.keys().copied().collect();
+        let (Some(&from_idx), Some(&to_idx)) = (keys.choose(rng), keys.choose(rng)) else {
+            return false;
+        };
+
+        // Edges run from an input, bias or hidden node to a hidden or output node,
+        // and never duplicate an existing edge. Edges that would close a loop
+        // (self connections included) are only allowed as recurrent edges.
+        let recurrent = self.creates_cycle(from_idx, to_idx);
+        let exists = self.connections.iter().any(|c| c.from_idx == from_idx && c.to_idx == to_idx);
+        if self.nodes[&from_idx] == NodeType::Output
+            || matches!(self.nodes[&to_idx], NodeType::Input | NodeType::Bias)
+            || exists
+            || (recurrent && !allow_recurrent)
+        {
+            return false;
+        }
+
+        let innovation = history.get_innovation(from_idx, to_idx);
+        let fan_in = self.connections.iter().filter(|c| c.enabled && c.to_idx == to_idx).count() + 1;
+        let weight = weight_init.sample(fan_in, rng);
+        self.connections.push(Connection {
+            from_idx, to_idx, weight, enabled: true, innovation, recurrent,
+        });
+        true
+    }
+
+    /// Splits a random enabled connection with a new hidden node. Returns
+    /// whether a node was added.
+    pub fn mutate_add_node(&mut self, history: &mut InnovationHistory, rng: &mut impl Rng) -> bool {
+        let Some(conn) = self.connections.iter_mut().filter(|c| c.enabled).choose(rng) else {
+            return false;
+        };
+        conn.enabled = false;
+        let (from_idx, to_idx, weight, recurrent) = (conn.from_idx, conn.to_idx, conn.weight, conn.recurrent);
+
+        // A genome that has split this edge before already owns the shared node
+        let mut new_id = history.split_node_id(from_idx, to_idx);
+        if self.nodes.contains_key(&new_id) {
+            new_id = history.next_node_id();
+        }
+        self.nodes.insert(new_id, NodeType::Hidden);
+
+        // Add two connections to replace the old one. Weight 1 going in and the
+        // old weight going out keeps the network's behaviour close to before.
+        // If the old edge looped back, the outgoing half is the one that does.
+        let innov1 = history.get_innovation(from_idx, new_id);
+        let innov2 = history.get_innovation(new_id, to_idx);
+        self.connections.push(Connection::new(from_idx, new_id, 1.0, innov1));
+        self.connections.push(Connection { recurrent, ..Connection::new(new_id, to_idx, weight, innov2) });
+        true
+    }
+
+    /// Keeps every weight within `[-cap, cap]`. Also useful for genomes that
+    /// didn't come out of `mutate`, e.g. ones loaded from disk.
+    pub fn clamp_weights(&mut self, cap: Float) {
+        for conn in &mut self.connections {
+            conn.weight = conn.weight.clamp(-cap, cap);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A genome holding just connections with these innovations and weights.
+    fn genes(genes: &[(usize, Float)]) -> Genome {
+        let mut genome = Genome::default();
+        for &(innovation, weight) in genes {
+            genome.connections.push(Connection::new(0, 1, weight, innovation));
+        }
+        genome
+    }
+
+    #[test]
+    fn distance_counts_excess_disjoint_and_weight_difference() {
+        // 3 and 4 are disjoint, 5 and 6 excess, and 0-2 match with weight
+        // differences 0.5, 1 and 0
+        let a = genes(&[(0, 0.5), (1, 1.0), (2, 0.0), (4, 0.0)]);
+        let b = genes(&[(0, 0.0), (1, 0.0), (2, 0.0), (3, 0.0), (5, 0.0), (6, 0.0)]);
+        let distance = a.compatibility_distance(&b, 1.0, 2.0, 3.0);
+        assert!((distance - (2.0 + 2.0 * 2.0 + 3.0 * 0.5)).abs() < 1e-6, "{distance}");
+        assert!((distance - b.compatibility_distance(&a, 1.0, 2.0, 3.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn activation_mutation_survives_a_text_round_trip() {
+        let mut history = InnovationHistory::default();
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut genome = Genome::minimal(2, 1, &mut history, &mut rng);
+        assert!(!genome.mutate_activation(&mut rng), "there's no hidden node yet");
+        genome.mutate_add_node(&mut history, &mut rng);
+        assert!(genome.mutate_activation(&mut rng));
+        let hidden = *genome.activations.keys().next().expect("the hidden node changed");
+        assert_ne!(genome.activation(hidden), Activation::Tanh);
+
+        let loaded = Genome::from_text(&genome.to_text()).unwrap();
+        assert!(loaded.structural_eq(&genome));
+        assert_eq!(loaded.compile().nodes.iter().find(|n| n.id == hidden).unwrap().activation, genome.activation(hidden));
+    }
+
+    #[test]
+    fn try_compile_reports_a_dangling_connection() {
+        let mut genome = Genome::minimal(2, 1, &mut InnovationHistory::default(), &mut StdRng::seed_from_u64(0));
+        genome.connections.push(Connection::new(0, 99, 1.0, 50));
+        let errors = genome.try_compile().err().expect("node 99 doesn't exist");
+        assert_eq!(errors, vec![GenomeError::MissingNode { innovation: 50, node: 99 }]);
+    }
+
+    #[test]
+    #[should_panic(expected = "connection 50 refers to missing node 99")]
+    fn compile_names_a_dangling_connection() {
+        let mut genome = Genome::minimal(2, 1, &mut InnovationHistory::default(), &mut StdRng::seed_from_u64(0));
+        genome.connections.push(Connection::new(0, 99, 1.0, 50));
+        genome.compile();
+    }
+
+    #[test]
+    fn try_compile_reports_missing_outputs() {
+        let mut genome = Genome::default();
+        genome.add_node(0, NodeType::Input);
+        genome.add_node(1, NodeType::Hidden);
+        genome.connections.push(Connection::new(0, 1, 1.0, 0));
+        assert_eq!(genome.try_compile().err(), Some(vec![GenomeError::NoOutputs]));
+    }
+
+    #[test]
+    fn malformed_text_is_an_error() {
+        assert!(Genome::from_text("node 0 sideways").is_err());
+        assert!(Genome::from_text("conn 0 1 heavy true 0 false").is_err());
+        assert!(Genome::from_text("node 3 hidden sparkly").is_err());
+    }
+
+    #[test]
+    fn identical_genomes_are_zero_apart() {
+        let a = genes(&[(0, 0.3), (1, -0.7), (4, 2.0)]);
+        assert_eq!(a.compatibility_distance(&a.clone(), 1.0, 1.0, 0.4), 0.0);
+    }
+
+    #[test]
+    fn small_genomes_are_not_normalised() {
+        // Under 20 genes N is 1, so one excess gene costs all of c1
+        let a = genes(&[(0, 0.0), (1, 0.0)]);
+        let b = genes(&[(0, 0.0), (1, 0.0), (2, 0.0)]);
+        assert_eq!(a.compatibility_distance(&b, 1.0, 1.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn large_genomes_are_normalised_by_the_larger_gene_count() {
+        let a = genes(&(0..25).map(|i| (i, 0.0)).collect::<Vec<_>>());
+        let b = genes(&(0..20).map(|i| (i, 0.0)).collect::<Vec<_>>());
+        let distance = a.compatibility_distance(&b, 1.0, 1.0, 0.4);
+        assert!((distance - 5.0 / 25.0).abs() < 1e-6, "{distance}");
+    }
+
+    #[test]
+    fn mutation_keeps_weights_within_the_cap() {
+        let mut history = InnovationHistory::default();
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut genome = Genome::minimal(3, 2, &mut history, &mut rng);
+        genome.connections[0].weight = 50.0;
+        genome.connections[1].weight = -50.0;
+        for _ in 0..500 {
+            genome.mutate_with_rng(&mut history, &MutationRates::default(), 2.0, false, WeightInit::Uniform { range: 10.0 }, &mut rng);
+            assert!(genome.connections.iter().all(|c| c.weight.abs() <= 2.0));
+        }
+    }
+
+    #[test]
+    fn clamp_weights_only_touches_weights_over_the_cap() {
+        let mut genome = genes(&[(0, 3.0), (1, -0.5), (2, -4.0)]);
+        genome.clamp_weights(1.0);
+        let weights: Vec<Float> = genome.connections.iter().map(|c| c.weight).collect();
+        assert_eq!(weights, vec![1.0, -0.5, -1.0]);
+    }
+
+    #[test]
+    fn add_connection_only_links_sources_to_targets() {
+        let mut history = InnovationHistory::default();
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut genome = Genome::with_connectivity(3, 3, Connectivity::None, &mut history, &mut rng)
+            .with_bias(WeightInit::default(), &mut history, &mut rng);
+        genome.mutate_add_node(&mut history, &mut rng);
+        for _ in 0..500 {
+            genome.mutate_add_connection(&mut history, true, WeightInit::default(), &mut rng);
+        }
+        assert!(!genome.connections.is_empty());
+        for conn in &genome.connections {
+            assert_ne!(genome.nodes[&conn.from_idx], NodeType::Output, "{conn:?}");
+            assert!(matches!(genome.nodes[&conn.to_idx], NodeType::Hidden | NodeType::Output), "{conn:?}");
+        }
+    }
+
+    #[test]
+    fn crossover_takes_unmatched_genes_from_the_fitter_parent() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let fitter = Genome { fitness: 2.0, ..genes(&[(0, 1.0), (1, 1.0), (2, 1.0)]) };
+        let weaker = Genome { fitness: 1.0, ..genes(&[(0, -1.0), (1, -1.0), (3, -1.0), (4, -1.0)]) };
+        let innovations = |genome: &Genome| -> Vec<usize> {
+            let mut innovations: Vec<usize> = genome.connections.iter().map(|c| c.innovation).collect();
+            innovations.sort();
+            innovations
+        };
+
+        assert_eq!(innovations(&weaker.crossover(&fitter, 0.75, &mut rng)), vec![0, 1, 2]);
+        // Equally fit parents both pass theirs on
+        let equal = Genome { fitness: 2.0, ..weaker.clone() };
+        assert_eq!(innovations(&fitter.crossover(&equal, 0.75, &mut rng)), vec![0, 1, 2, 3, 4]);
+
+        // Matching genes come from either side
+        let matching: Vec<Float> = (0..50).map(|_| fitter.crossover(&weaker, 0.75, &mut rng).connections[0].weight).collect();
+        assert!(matching.contains(&1.0) && matching.contains(&-1.0), "{matching:?}");
+    }
+
+    #[test]
+    fn crossover_keeps_genes_disabled_in_either_parent_disabled_by_chance() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let enabled = genes(&[(0, 1.0)]);
+        let mut disabled = enabled.clone();
+        disabled.connections[0].enabled = false;
+        for (chance, expected) in [(1.0, false), (0.0, true)] {
+            for _ in 0..20 {
+                assert_eq!(enabled.crossover(&disabled, chance, &mut rng).connections[0].enabled, expected);
+                assert_eq!(disabled.crossover(&enabled, chance, &mut rng).connections[0].enabled, expected);
+            }
+        }
+        // Enabled in both stays enabled whatever the chance
+        assert!(enabled.crossover(&enabled, 1.0, &mut rng).connections[0].enabled);
+    }
+
+    #[test]
+    fn hidden_seeds_sit_between_every_input_and_output() {
+        let mut history = InnovationHistory::default();
+        let mut rng = StdRng::seed_from_u64(6);
+        let seeded = |history: &mut InnovationHistory, rng: &mut StdRng| {
+            Genome::with_hidden(2, 2, Connectivity::None, 3, WeightInit::default(), history, rng)
+        };
+        let genome = seeded(&mut history, &mut rng);
+        let links: HashSet<(usize, usize)> = genome.connections.iter().map(|c| (c.from_idx, c.to_idx)).collect();
+        let expected: HashSet<(usize, usize)> = (4..7)
+            .flat_map(|hidden| [(0, hidden), (1, hidden), (hidden, 2), (hidden, 3)])
+            .collect();
+        assert_eq!(links, expected);
+        assert!(genome.validate().is_ok());
+        // Direct links come on top, as the connectivity says
+        let full = Genome::with_hidden(2, 2, Connectivity::Full, 3, WeightInit::default(), &mut history, &mut rng);
+        assert_eq!(full.connections.len(), expected.len() + 4);
+
+        // Genomes seeded alike line up gene for gene
+        let other = seeded(&mut history, &mut rng);
+        let innovations = |genome: &Genome| genome.connections.iter().map(|c| c.innovation).collect::<Vec<_>>();
+        assert_eq!(innovations(&genome), innovations(&other));
+        assert!(genome.structural_eq(&other));
+
+        // And hidden nodes grown later don't reuse the seeds' ids
+        let mut grown = genome.clone();
+        assert!(grown.mutate_add_node(&mut history, &mut rng));
+        assert!(grown.nodes.keys().filter(|id| !genome.nodes.contains_key(id)).all(|&id| id >= 7));
+    }
+
+    #[test]
+    fn diff_lists_every_change() {
+        let before = Genome::from_text(
+            "node 0 input\nnode 1 output\nnode 2 hidden\n\
+            conn 0 1 0.5 true 0 false\nconn 0 2 1 true 1 false\nconn 2 1 1 true 2 false\n",
+        ).unwrap();
+        let after = Genome::from_text(
+            "node 0 input\nnode 1 output\nnode 3 hidden\n\
+            conn 0 1 0.75 false 0 false\nconn 0 3 1 true 3 false\nconn 3 1 1 true 4 false\n",
+        ).unwrap();
+        let diff = before.diff(&after);
+        assert_eq!(diff.added_nodes, vec![(3, NodeType::Hidden)]);
+        assert_eq!(diff.removed_nodes, vec![(2, NodeType::Hidden)]);
+        let innovations = |conns: &[Connection]| conns.iter().map(|c| c.innovation).collect::<Vec<_>>();
+        assert_eq!(innovations(&diff.added_connections), vec![3, 4]);
+        assert_eq!(innovations(&diff.removed_connections), vec![1, 2]);
+        assert_eq!(diff.toggled_connections, vec![(0, false)]);
+        assert_eq!(diff.weight_changes, vec![(0, 0.5, 0.75)]);
+        assert_eq!(diff.to_string().lines().count(), 8);
+
+        // Going back undoes it all
+        let back = after.diff(&before);
+        assert_eq!(back.added_nodes, diff.removed_nodes);
+        assert_eq!(back.weight_changes, vec![(0, 0.75, 0.5)]);
+        assert!(before.diff(&before).is_empty());
+        assert_eq!(before.diff(&before).to_string(), "no differences\n");
+    }
+
+    /// Mean and standard deviation of `weights`.
+    fn spread(weights: &[Float]) -> (Float, Float) {
+        let mean = weights.iter().sum::<Float>() / weights.len() as Float;
+        let variance = weights.iter().map(|w| (w - mean).powi(2)).sum::<Float>() / weights.len() as Float;
+        (mean, variance.sqrt())
+    }
+
+    #[test]
+    fn weight_init_draws_from_its_distribution() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut draw = |init: WeightInit, fan_in| (0..20_000).map(|_| init.sample(fan_in, &mut rng)).collect::<Vec<_>>();
+
+        let uniform = draw(WeightInit::Uniform { range: 3.0 }, 1);
+        assert!(uniform.iter().all(|w| (-3.0..3.0).contains(w)));
+        let (mean, std) = spread(&uniform);
+        assert!(mean.abs() < 0.1 && (std - 3.0 / (3.0 as Float).sqrt()).abs() < 0.05, "{mean} {std}");
+
+        let (mean, std) = spread(&draw(WeightInit::Gaussian { std: 2.0 }, 1));
+        assert!(mean.abs() < 0.1 && (std - 2.0).abs() < 0.05, "{mean} {std}");
+
+        for fan_in in [1, 4, 100] {
+            let (mean, std) = spread(&draw(WeightInit::Xavier, fan_in));
+            let expected = 1.0 / (fan_in as Float).sqrt();
+            assert!(mean.abs() < 0.05 && (std - expected).abs() < 0.05 * expected, "{fan_in}: {mean} {std}");
+        }
+    }
+
+    #[test]
+    fn starting_genomes_scale_xavier_weights_by_final_fan_in() {
+        let mut history = InnovationHistory::default();
+        let mut rng = StdRng::seed_from_u64(8);
+        let genome = Genome::with_hidden(400, 1, Connectivity::Full, 0, WeightInit::Xavier, &mut history, &mut rng);
+        let weights: Vec<Float> = genome.connections.iter().map(|c| c.weight).collect();
+        let (_, std) = spread(&weights);
+        assert!((std - 0.05).abs() < 0.01, "{std}");
+    }
+}
@@ -0,0 +1,130 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::Float;
+use crate::genome::Genome;
+
+/// The best genomes seen over a whole run, kept by their `fitness`, best first.
+/// Survives anything that happens to the population itself.
+#[derive(Debug, Clone)]
+pub struct HallOfFame {
+    capacity: usize,
+    genomes: Vec<Genome>,
+}
+
+impl HallOfFame {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            genomes: Vec::new(),
+        }
+    }
+
+    pub fn genomes(&self) -> &[Genome] {
+        &self.genomes
+    }
+
+    pub fn best(&self) -> Option<&Genome> {
+        self.genomes.first()
+    }
+
+    /// Fitness a genome has to beat to get in, or `None` while there's room.
+    pub fn floor(&self) -> Option<Float> {
+        if self.genomes.len() < self.capacity {
+            None
+        } else {
+            self.genomes.last().map(|g| g.fitness)
+        }
+    }
+
+    /// Adds a copy of `genome` if it beats the floor, pushing out the worst
+    /// entry when full. A genome `Genome::structural_eq` to one already in
+    /// only replaces it when fitter, so a champion kept for generations
+    /// doesn't fill every place. Returns whether it got in.
+    pub fn consider(&mut self, genome: &Genome) -> bool {
+        if self.capacity == 0 || self.floor().is_some_and(|floor| genome.fitness <= floor) {
+            return false;
+        }
+        if let Some(twin) = self.genomes.iter().position(|g| g.structural_eq(genome)) {
+            if self.genomes[twin].fitness >= genome.fitness {
+                return false;
+            }
+            self.genomes.remove(twin);
+        }
+        let idx = self.genomes.partition_point(|g| g.fitness >= genome.fitness);
+        self.genomes.insert(idx, genome.clone());
+        self.genomes.truncate(self.capacity);
+        true
+    }
+
+    /// Writes every entry with `Genome::to_text`, separated by `---` lines.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let text = self.genomes.iter()
+            .map(Genome::to_text)
+            .collect::<Vec<_>>()
+            .join("---\n");
+        fs::write(path, text)
+    }
+
+    /// Reads a file written by `save`. Entries past `capacity` are dropped.
+    pub fn load(path: impl AsRef<Path>, capacity: usize) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut hall = Self::new(capacity);
+        for block in text.split("---").filter(|b| !b.trim().is_empty()) {
+            let genome = Genome::from_text(block)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            hall.consider(&genome);
+        }
+        Ok(hall)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+    use crate::innovation::InnovationHistory;
+
+    fn scored(genome: &Genome, fitness: Float) -> Genome {
+        Genome { fitness, ..genome.clone() }
+    }
+
+    #[test]
+    fn structural_twins_keep_the_higher_fitness() {
+        let mut history = InnovationHistory::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        let champion = Genome::minimal(2, 1, &mut history, &mut rng);
+        let mut other = champion.clone();
+        other.mutate_add_node(&mut history, &mut rng);
+
+        let mut hall = HallOfFame::new(3);
+        assert!(hall.consider(&scored(&champion, 1.0)));
+        assert!(!hall.consider(&scored(&champion, 0.5)));
+        assert!(hall.consider(&scored(&other, 0.8)));
+        // Same structure, new weights, better score: replaces its twin
+        let mut reweighted = scored(&champion, 2.0);
+        reweighted.connections[0].weight += 1.0;
+        assert!(hall.consider(&reweighted));
+
+        let fitnesses: Vec<Float> = hall.genomes().iter().map(|g| g.fitness).collect();
+        assert_eq!(fitnesses, vec![2.0, 0.8]);
+    }
+
+    #[test]
+    fn full_hall_keeps_the_best_in_order() {
+        let mut history = InnovationHistory::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut genome = Genome::minimal(2, 1, &mut history, &mut rng);
+        let mut hall = HallOfFame::new(2);
+        for fitness in [1.0, 3.0, 2.0, 0.5] {
+            genome.mutate_add_node(&mut history, &mut rng);
+            hall.consider(&scored(&genome, fitness));
+        }
+        let fitnesses: Vec<Float> = hall.genomes().iter().map(|g| g.fitness).collect();
+        assert_eq!(fitnesses, vec![3.0, 2.0]);
+        assert_eq!(hall.floor(), Some(2.0));
+    }
+}
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+/// Single source of truth for innovation numbers and node ids, shared by every
+/// genome in a run. Keep one alive for the whole run; the counters only ever
+/// climb.
+#[derive(Debug, Default)]
+pub struct InnovationHistory {
+    pub map: HashMap<(usize, usize), usize>, // (from, to) -> innovation_id
+    pub splits: HashMap<(usize, usize), usize>, // (from, to) split this generation -> new node id
+    pub next_innovation: usize,
+    pub next_node_id: usize,
+}
+
+impl InnovationHistory {
+    pub fn get_innovation(&mut self, from: usize, to: usize) -> usize {
+        if let Some(&id) = self.map.get(&(from, to)) {
+            id
+        } else {
+            let id = self.next_innovation;
+            self.map.insert((from, to), id);
+            self.next_innovation += 1;
+            id
+        }
+    }
+
+    pub fn next_node_id(&mut self) -> usize {
+        let id = self.next_node_id;
+        self.next_node_id += 1;
+        id
+    }
+
+    /// Node id for splitting the connection `from -> to`. Genomes making the
+    /// same split in the same generation get the same node, and so the same
+    /// innovation numbers for the two new connections.
+    pub fn split_node_id(&mut self, from: usize, to: usize) -> usize {
+        if let Some(&id) = self.splits.get(&(from, to)) {
+            id
+        } else {
+            let id = self.next_node_id();
+            self.splits.insert((from, to), id);
+            id
+        }
+    }
+
+    /// Forgets this generation's splits so the next one's get fresh nodes.
+    pub fn new_generation(&mut self) {
+        self.splits.clear();
+    }
+}
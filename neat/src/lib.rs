@@ -0,0 +1,53 @@
+//! NEAT genomes, compiled networks and the generational engine shared by the
+//! `bobbles` sim and the `BasicNNTest` XOR runner.
+
+/// Precision of weights, activations and fitness throughout the crate: `f32`,
+/// or `f64` with the `f64` feature.
+#[cfg(not(feature = "f64"))]
+pub type Float = f32;
+#[cfg(feature = "f64")]
+pub type Float = f64;
+
+mod genome;
+mod hall_of_fame;
+mod innovation;
+mod metrics;
+mod network;
+mod pareto;
+mod population;
+mod problems;
+mod species;
+
+pub use genome::{Activation, Connection, Connectivity, Genome, GenomeDiff, GenomeError, MutationRates, NodeType, WeightInit};
+pub use hall_of_fame::HallOfFame;
+pub use innovation::InnovationHistory;
+pub use metrics::{GenerationCallback, GenerationReport, GenerationStats, MetricsCsv};
+pub use network::{Layers, NeuralNetwork, NodeState, OutputMode};
+pub use pareto::{crowding_distances, non_dominated_fronts, pareto_scores};
+pub use population::{Neat, NeatConfig, ThresholdTarget};
+pub use problems::{Dataset, FitnessFn, FitnessShaping, MultiObjectiveFitness, Parsimonious};
+pub use species::Species;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn float_follows_the_f64_feature() {
+        assert_eq!(size_of::<Float>(), if cfg!(feature = "f64") { 8 } else { 4 });
+    }
+
+    #[test]
+    fn networks_compute_at_full_precision() {
+        let mut genome = Genome::default();
+        genome.add_node(0, NodeType::Input);
+        genome.add_node(1, NodeType::Output);
+        genome.add_connection(0, 1, 1.0 + 1e-10, &mut InnovationHistory::default());
+        let output = genome.compile().with_output_mode(OutputMode::Raw).activate(&[1.0])[0];
+        // Lost in an f32, kept in an f64
+        assert_eq!(output == 1.0, cfg!(not(feature = "f64")));
+
+        let loaded = Genome::from_text(&genome.to_text()).unwrap();
+        assert_eq!(loaded.connections[0].weight, genome.connections[0].weight);
+    }
+}
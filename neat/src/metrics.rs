@@ -0,0 +1,54 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::Float;
+use crate::genome::Genome;
+
+/// Summary of one scored generation, as returned by `Neat::evolve`.
+#[derive(Debug, Clone, Copy)]
+pub struct GenerationStats {
+    pub generation: usize,
+    pub best_fitness: Float,
+    pub mean_fitness: Float,
+    pub species_count: usize,
+    pub population_size: usize,
+    pub reached_target: bool, // Best fitness so far meets NeatConfig::target_fitness
+    pub non_finite: usize, // Genomes that scored NaN or infinity, which count as 0
+}
+
+/// What `Neat::set_generation_callback` sees after each generation: its stats
+/// and its best genome, which is `None` only for an empty population.
+#[derive(Debug, Clone, Copy)]
+pub struct GenerationReport<'a> {
+    pub stats: &'a GenerationStats,
+    pub best_genome: Option<&'a Genome>,
+}
+
+pub type GenerationCallback = Box<dyn FnMut(&GenerationReport)>;
+
+/// Appends one CSV row per generation, flushing each so a killed run still
+/// leaves its data behind.
+pub struct MetricsCsv {
+    writer: BufWriter<File>,
+}
+
+impl MetricsCsv {
+    /// Creates (or truncates) `path` and writes the header row.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "generation,best_fitness,mean_fitness,species_count,population_size,non_finite")?;
+        writer.flush()?;
+        Ok(Self { writer })
+    }
+
+    pub fn append(&mut self, stats: &GenerationStats) -> io::Result<()> {
+        writeln!(
+            self.writer,
+            "{},{},{},{},{},{}",
+            stats.generation, stats.best_fitness, stats.mean_fitness, stats.species_count, stats.population_size,
+            stats.non_finite,
+        )?;
+        self.writer.flush()
+    }
+}
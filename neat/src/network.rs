@@ -0,0 +1,440 @@
+use crate::Float;
+use crate::genome::{Activation, NodeType};
+
+pub struct NodeState {
+    pub id: usize,
+    pub value: Float,
+    pub incoming: Vec<(usize, Float)>, // (index_in_nodes_vec, weight)
+    pub recurrent: Vec<(usize, Float)>, // Same, but read from the previous activation
+    pub node_type: NodeType,
+    pub activation: Activation, // Only hidden nodes use it
+}
+
+/// Weighted sums are clamped to this before activation, so huge weights
+/// saturate rather than overflow. Raw outputs are bounded by it too.
+const MAX_SUM: Float = 1.0e6;
+
+/// How output nodes turn their weighted sum into a value. Hidden nodes use
+/// their own `Activation`. Sigmoid and tanh are both steepened by
+/// `NeuralNetwork::steepness`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputMode {
+    /// The weighted sum as is.
+    Raw,
+    /// Squashed into 0 to 1.
+    Sigmoid,
+    /// Squashed into -1 to 1.
+    #[default]
+    Tanh,
+    /// Normalised jointly across all outputs into probabilities that sum to 1.
+    Softmax,
+}
+
+/// A feed-forward network grouped into layers by each node's longest path
+/// from the inputs, so a whole layer can be computed as one matrix product.
+/// Built by `NeuralNetwork::to_layers`.
+#[derive(Debug, Clone)]
+pub struct Layers {
+    /// Indices into `NeuralNetwork::nodes` for each layer, inputs and bias first.
+    pub nodes: Vec<Vec<usize>>,
+    /// `weights[k][row][col]` is the weight into node `row` of layer `k + 1`
+    /// from column `col` of every earlier layer's nodes laid end to end.
+    /// Connections can skip layers, so each layer reads all the ones before it.
+    pub weights: Vec<Vec<Vec<Float>>>,
+}
+
+/// A genome compiled into a flat list of nodes plus the order to evaluate them
+/// in. Build one with `Genome::compile`.
+pub struct NeuralNetwork {
+    pub nodes: Vec<NodeState>,
+    pub(crate) execution_order: Vec<usize>,
+    pub(crate) inputs_count: usize,
+    pub(crate) output_indices: Vec<usize>,
+    pub(crate) has_recurrent: bool,
+    pub output_mode: OutputMode,
+    /// Multiplies the weighted sum before sigmoid or tanh squash it, so higher
+    /// values make neurons switch more sharply. 1.0 leaves them as they are;
+    /// the original NEAT used a sigmoid steepened by 4.9.
+    pub steepness: Float,
+}
+
+impl NeuralNetwork {
+    pub fn inputs_count(&self) -> usize {
+        self.inputs_count
+    }
+
+    /// The same network with its outputs computed using `output_mode`.
+    pub fn with_output_mode(mut self, output_mode: OutputMode) -> Self {
+        self.output_mode = output_mode;
+        self
+    }
+
+    /// The same network with its sigmoid and tanh steepened by `steepness`.
+    pub fn with_steepness(mut self, steepness: Float) -> Self {
+        self.steepness = steepness;
+        self
+    }
+
+    /// Indices into `nodes` in the order `activate` computes them.
+    pub fn execution_order(&self) -> &[usize] {
+        &self.execution_order
+    }
+
+    /// Splits the network into layers and dense weight matrices, see `Layers`.
+    /// Nodes that don't lead to an output are left out. Fails for networks with
+    /// recurrent connections, which have no layering.
+    pub fn to_layers(&self) -> Result<Layers, String> {
+        if self.has_recurrent {
+            return Err("recurrent networks can't be split into layers".to_string());
+        }
+
+        let mut depth = vec![0; self.nodes.len()];
+        let mut nodes = vec![Vec::new()];
+        for (idx, node) in self.nodes.iter().enumerate() {
+            if matches!(node.node_type, NodeType::Input | NodeType::Bias) {
+                nodes[0].push(idx);
+            }
+        }
+        // Execution order is topological, so every source's depth is already known
+        for &idx in &self.execution_order {
+            let d = self.nodes[idx].incoming.iter().map(|(from_idx, _)| depth[*from_idx]).max().unwrap_or(0) + 1;
+            depth[idx] = d;
+            if nodes.len() <= d {
+                nodes.resize(d + 1, Vec::new());
+            }
+            nodes[d].push(idx);
+        }
+
+        let mut weights = Vec::new();
+        let mut columns: Vec<usize> = Vec::new();
+        for k in 1..nodes.len() {
+            columns.extend(&nodes[k - 1]);
+            let matrix = nodes[k].iter().map(|&row| {
+                let mut weights = vec![0.0; columns.len()];
+                for (from_idx, weight) in &self.nodes[row].incoming {
+                    let col = columns.iter().position(|c| c == from_idx).expect("sources sit in earlier layers");
+                    weights[col] += weight;
+                }
+                weights
+            }).collect();
+            weights.push(matrix);
+        }
+        Ok(Layers { nodes, weights })
+    }
+
+    /// Writes the network out as the source of a standalone Rust function
+    /// `pub fn name(inputs: &[Float]) -> Vec<Float>` with the topology and
+    /// weights baked in, so it can be used without this crate. It computes
+    /// exactly what `activate` does on a freshly reset network. Fails for
+    /// networks with recurrent connections, which would need state between
+    /// calls.
+    pub fn to_rust(&self, name: &str) -> Result<String, String> {
+        if self.has_recurrent {
+            return Err("recurrent networks can't be exported as a function".to_string());
+        }
+        let ty = std::any::type_name::<Float>();
+        let literal = |x: Float| {
+            if x.is_finite() {
+                format!("{x:?}_{ty}")
+            } else if x.is_nan() {
+                format!("{ty}::NAN")
+            } else {
+                format!("{}{ty}::INFINITY", if x < 0.0 { "-" } else { "" })
+            }
+        };
+
+        let mut src = String::new();
+        src.push_str(&format!("pub fn {name}(inputs: &[{ty}]) -> Vec<{ty}> {{\n"));
+        src.push_str(&format!("    assert_eq!(inputs.len(), {});\n", self.inputs_count));
+        src.push_str(&format!(
+            "    let squash = |sum: {ty}| if sum.is_nan() {{ 0.0 }} else {{ sum.clamp({}, {}) }};\n",
+            literal(-MAX_SUM), literal(MAX_SUM),
+        ));
+        let steepness = literal(self.steepness);
+        src.push_str(&format!("    let mut v = [0.0_{ty}; {}];\n", self.nodes.len().max(1)));
+        let mut input_ptr = 0;
+        for (idx, node) in self.nodes.iter().enumerate() {
+            match node.node_type {
+                NodeType::Input => {
+                    src.push_str(&format!("    v[{idx}] = inputs[{input_ptr}];\n"));
+                    input_ptr += 1;
+                }
+                NodeType::Bias => src.push_str(&format!("    v[{idx}] = 1.0;\n")),
+                _ => {}
+            }
+        }
+        for &idx in &self.execution_order {
+            let node = &self.nodes[idx];
+            // Same terms in the same order as `activate`, so rounding matches too
+            let terms: Vec<String> = node.incoming.iter()
+                .map(|(from_idx, weight)| format!("v[{from_idx}] * {}", literal(*weight)))
+                .collect();
+            let sum = if terms.is_empty() { format!("0.0_{ty}") } else { terms.join(" + ") };
+            let value = match (node.node_type, self.output_mode) {
+                (NodeType::Output, OutputMode::Raw | OutputMode::Softmax) => format!("squash({sum})"),
+                (NodeType::Output, OutputMode::Sigmoid) => format!("1.0 / (1.0 + (-({steepness} * squash({sum}))).exp())"),
+                (NodeType::Output, OutputMode::Tanh) => format!("({steepness} * squash({sum})).tanh()"),
+                _ => node.activation.to_rust(&format!("{steepness} * squash({sum})")),
+            };
+            src.push_str(&format!("    v[{idx}] = {value};\n"));
+        }
+
+        let outputs: Vec<String> = self.output_indices.iter().map(|i| format!("v[{i}]")).collect();
+        if self.output_mode == OutputMode::Softmax && !outputs.is_empty() {
+            src.push_str(&format!("    let outputs = [{}];\n", outputs.join(", ")));
+            src.push_str(&format!("    let max = outputs.iter().copied().fold({ty}::MIN, {ty}::max);\n"));
+            src.push_str(&format!("    let exps: Vec<{ty}> = outputs.iter().map(|o| (o - max).exp()).collect();\n"));
+            src.push_str(&format!("    let total: {ty} = exps.iter().sum();\n"));
+            src.push_str("    exps.iter().map(|e| e / total).collect()\n");
+        } else {
+            src.push_str(&format!("    vec![{}]\n", outputs.join(", ")));
+        }
+        src.push_str("}\n");
+        Ok(src)
+    }
+
+    /// Feeds `inputs` through the network and returns the output values.
+    /// Panics unless there's exactly one input per input node.
+    pub fn activate(&mut self, inputs: &[Float]) -> Vec<Float> {
+        assert_eq!(
+            inputs.len(), self.inputs_count,
+            "network has {} inputs but was given {}", self.inputs_count, inputs.len(),
+        );
+        let mut input_ptr = 0;
+        for node in &mut self.nodes {
+            match node.node_type {
+                NodeType::Input => {
+                    node.value = inputs[input_ptr];
+                    input_ptr += 1;
+                }
+                // `reset` zeroes it along with everything else
+                NodeType::Bias => node.value = 1.0,
+                _ => {}
+            }
+        }
+
+        // Recurrent edges see last activation's values, so snapshot them before
+        // this pass starts overwriting nodes.
+        let previous: Vec<Float> = if self.has_recurrent {
+            self.nodes.iter().map(|n| n.value).collect()
+        } else {
+            Vec::new()
+        };
+
+        for &idx in &self.execution_order {
+            let node = &self.nodes[idx];
+            let sum: Float = node.incoming.iter()
+                .map(|(from_idx, weight)| self.nodes[*from_idx].value * weight)
+                .chain(node.recurrent.iter().map(|(from_idx, weight)| previous[*from_idx] * weight))
+                .sum();
+            // Infinite terms of opposite sign sum to NaN; neither should leak on
+            let sum = if sum.is_nan() { 0.0 } else { sum.clamp(-MAX_SUM, MAX_SUM) };
+            self.nodes[idx].value = match (node.node_type, self.output_mode) {
+                (NodeType::Output, OutputMode::Raw | OutputMode::Softmax) => sum,
+                (NodeType::Output, OutputMode::Sigmoid) => 1.0 / (1.0 + (-(self.steepness * sum)).exp()),
+                (NodeType::Output, OutputMode::Tanh) => (self.steepness * sum).tanh(), // Using Tanh for -1 to 1 output
+                _ => node.activation.apply(self.steepness * sum),
+            };
+        }
+
+        // Softmax needs every output's sum, so it's applied once they're all in.
+        // Nodes reading an output in the same pass saw the raw sum.
+        if self.output_mode == OutputMode::Softmax && !self.output_indices.is_empty() {
+            let max = self.output_indices.iter().map(|&i| self.nodes[i].value).fold(Float::MIN, Float::max);
+            let exps: Vec<Float> = self.output_indices.iter().map(|&i| (self.nodes[i].value - max).exp()).collect();
+            let total: Float = exps.iter().sum();
+            for (&i, e) in self.output_indices.iter().zip(exps) {
+                self.nodes[i].value = e / total;
+            }
+        }
+
+        self.output_indices.iter().map(|&i| self.nodes[i].value).collect()
+    }
+
+    /// Runs the network `steps` times on the same `inputs` and returns the
+    /// last outputs, so signal has time to travel round recurrent loops.
+    /// Feed-forward networks settle in one pass, so they only ever take one.
+    pub fn activate_steps(&mut self, inputs: &[Float], steps: usize) -> Vec<Float> {
+        let steps = if self.has_recurrent { steps.max(1) } else { 1 };
+        let mut outputs = Vec::new();
+        for _ in 0..steps {
+            outputs = self.activate(inputs);
+        }
+        outputs
+    }
+
+    /// Whether any connection reads the previous activation's values.
+    pub fn is_recurrent(&self) -> bool {
+        self.has_recurrent
+    }
+
+    /// Runs `activate` once per input row, clearing node values between rows
+    /// so each row is evaluated independently. Same results as calling
+    /// `activate` row by row on a fresh network.
+    pub fn activate_batch(&mut self, inputs: &[Vec<Float>]) -> Vec<Vec<Float>> {
+        inputs.iter().map(|row| {
+            self.reset();
+            self.activate(row)
+        }).collect()
+    }
+
+    /// Zeroes every node value so the next `activate` starts from a clean
+    /// state; bias nodes go back to 1 when it runs. Feed-forward nets
+    /// overwrite everything they read anyway, but once connections can be
+    /// recurrent, values carry over between calls. Fitness functions
+    /// evaluating independent episodes or sequences should call this between
+    /// them.
+    pub fn reset(&mut self) {
+        for node in &mut self.nodes {
+            node.value = 0.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+    use crate::genome::{Connectivity, Genome, WeightInit};
+    use crate::innovation::InnovationHistory;
+
+    /// `inputs` fully connected to `outputs` with seeded random weights.
+    fn network(inputs: usize, outputs: usize, seed: u64) -> NeuralNetwork {
+        Genome::minimal(inputs, outputs, &mut InnovationHistory::default(), &mut StdRng::seed_from_u64(seed)).compile()
+    }
+
+    #[test]
+    fn softmax_outputs_are_probabilities() {
+        let mut network = network(3, 4, 1).with_output_mode(OutputMode::Softmax);
+        for inputs in [[0.0, 0.0, 0.0], [1.0, -2.0, 0.5], [1.0e9, -1.0e9, 3.0]] {
+            let outputs = network.activate(&inputs);
+            let total: Float = outputs.iter().sum();
+            assert!((total - 1.0).abs() < 1e-5, "{outputs:?}");
+            assert!(outputs.iter().all(|o| (0.0..=1.0).contains(o)), "{outputs:?}");
+        }
+    }
+
+    #[test]
+    fn huge_and_non_finite_weights_give_finite_outputs() {
+        let mut network = network(2, 3, 0);
+        for (node, weights) in network.nodes.iter_mut().filter(|n| n.node_type == NodeType::Output).zip([
+            [Float::INFINITY, Float::NEG_INFINITY],
+            [Float::MAX, Float::MAX],
+            [Float::NAN, 1.0],
+        ]) {
+            for ((_, weight), new) in node.incoming.iter_mut().zip(weights) {
+                *weight = new;
+            }
+        }
+        for mode in [OutputMode::Raw, OutputMode::Sigmoid, OutputMode::Tanh, OutputMode::Softmax] {
+            network.output_mode = mode;
+            let outputs = network.activate(&[1.0, 1.0]);
+            assert!(outputs.iter().all(|o| o.is_finite()), "{mode:?}: {outputs:?}");
+        }
+    }
+
+    /// Input 0 into an identity hidden node 2 that feeds itself back, out to
+    /// raw output 1, so each pass adds the input once more.
+    fn accumulator() -> NeuralNetwork {
+        let text = "node 0 input\nnode 1 output\nnode 2 hidden identity\n\
+            conn 0 2 1 true 0 false\nconn 2 2 1 true 1 true\nconn 2 1 1 true 2 false\n";
+        Genome::from_text(text).unwrap().compile().with_output_mode(OutputMode::Raw)
+    }
+
+    #[test]
+    fn recurrent_networks_take_the_steps_they_are_given() {
+        let mut network = accumulator();
+        assert!(network.is_recurrent());
+        assert_eq!(network.activate_steps(&[1.0], 3), vec![3.0]);
+        // Carries on from where it left off, until reset
+        assert_eq!(network.activate_steps(&[1.0], 2), vec![5.0]);
+        network.reset();
+        assert_eq!(network.activate_steps(&[1.0], 0), vec![1.0]);
+    }
+
+    #[test]
+    fn feed_forward_networks_take_one_step() {
+        let mut network = network(2, 1, 0);
+        let once = network.activate(&[0.5, -0.5]);
+        assert!(!network.is_recurrent());
+        assert_eq!(network.activate_steps(&[0.5, -0.5], 10), once);
+    }
+
+    /// Runs `network`'s `to_rust` export on each of `inputs` by compiling it
+    /// with rustc, giving back the outputs. They're printed in full, so they
+    /// read back exactly.
+    fn run_export(network: &NeuralNetwork, inputs: &[Vec<Float>]) -> Vec<Vec<Float>> {
+        let ty = std::any::type_name::<Float>();
+        let mut src = network.to_rust("net").unwrap();
+        src.push_str("fn main() {\n");
+        for row in inputs {
+            let row: Vec<String> = row.iter().map(|x| format!("{x:?}_{ty}")).collect();
+            src.push_str(&format!(
+                "    println!(\"{{}}\", net(&[{}]).iter().map(|o| format!(\"{{o:?}}\")).collect::<Vec<_>>().join(\" \"));\n",
+                row.join(", "),
+            ));
+        }
+        src.push_str("}\n");
+
+        let dir = std::env::temp_dir().join(format!("neat-export-{}-{:?}", std::process::id(), std::thread::current().id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("net.rs"), src).unwrap();
+        let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+        let built = std::process::Command::new(rustc)
+            .current_dir(&dir)
+            .args(["--edition", "2021", "-O", "net.rs", "-o", "net"])
+            .output()
+            .unwrap();
+        assert!(built.status.success(), "{}", String::from_utf8_lossy(&built.stderr));
+        let run = std::process::Command::new(dir.join("net")).output().unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        String::from_utf8(run.stdout).unwrap()
+            .lines()
+            .map(|line| line.split_whitespace().map(|output| output.parse().unwrap()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn the_export_computes_what_activate_does() {
+        let mut history = InnovationHistory::default();
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut genome = Genome::with_hidden(3, 2, Connectivity::Full, 4, WeightInit::Uniform { range: 2.0 }, &mut history, &mut rng)
+            .with_bias(WeightInit::default(), &mut history, &mut rng);
+        // One hidden node of each kind
+        for (id, activation) in (5..9).zip(crate::genome::Activation::ALL) {
+            genome.activations.insert(id, activation);
+        }
+        let inputs = vec![vec![0.0, 0.0, 0.0], vec![1.0, -0.5, 0.25], vec![-3.0, 2.0, 1.0e9]];
+
+        for mode in [OutputMode::Raw, OutputMode::Sigmoid, OutputMode::Tanh, OutputMode::Softmax] {
+            let mut network = genome.compile().with_output_mode(mode).with_steepness(1.5);
+            let expected: Vec<Vec<Float>> = inputs.iter()
+                .map(|row| {
+                    network.reset();
+                    network.activate(row)
+                })
+                .collect();
+            assert_eq!(run_export(&network, &inputs), expected, "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn recurrent_networks_cant_be_exported() {
+        assert!(accumulator().to_rust("net").is_err());
+    }
+
+    #[test]
+    fn steepness_scales_the_sum_before_squashing() {
+        let genome = Genome::from_text("node 0 input\nnode 1 output\nconn 0 1 1 true 0 false\n").unwrap();
+        let output = |mode, steepness| genome.compile().with_output_mode(mode).with_steepness(steepness).activate(&[0.5])[0];
+        assert_eq!(genome.compile().steepness, 1.0);
+
+        let close = |a: Float, b: Float| (a - b).abs() < 1e-6;
+        assert!(close(output(OutputMode::Sigmoid, 1.0), 1.0 / (1.0 + (-0.5 as Float).exp())));
+        assert!(close(output(OutputMode::Sigmoid, 4.9), 1.0 / (1.0 + (-2.45 as Float).exp())));
+        assert!(close(output(OutputMode::Tanh, 4.9), (2.45 as Float).tanh()));
+        assert_eq!(output(OutputMode::Raw, 4.9), 0.5);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::Float;
+
+/// Whether `a` is at least as good as `b` on every objective and better on
+/// one. Higher is better.
+fn dominates(a: &[Float], b: &[Float]) -> bool {
+    a.iter().zip(b).all(|(x, y)| x >= y) && a.iter().zip(b).any(|(x, y)| x > y)
+}
+
+/// Splits the scores into Pareto fronts, best first: the first holds
+/// everything no other score dominates, the next everything only the first
+/// dominates, and so on. Each front lists indices into `objectives`.
+pub fn non_dominated_fronts(objectives: &[Vec<Float>]) -> Vec<Vec<usize>> {
+    let n = objectives.len();
+    let mut dominated_by = vec![0; n];
+    let mut dominating: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for a in 0..n {
+        for b in a + 1..n {
+            if dominates(&objectives[a], &objectives[b]) {
+                dominating[a].push(b);
+                dominated_by[b] += 1;
+            } else if dominates(&objectives[b], &objectives[a]) {
+                dominating[b].push(a);
+                dominated_by[a] += 1;
+            }
+        }
+    }
+
+    let mut fronts = Vec::new();
+    let mut front: Vec<usize> = (0..n).filter(|&i| dominated_by[i] == 0).collect();
+    while !front.is_empty() {
+        let mut next = Vec::new();
+        for &i in &front {
+            for &j in &dominating[i] {
+                dominated_by[j] -= 1;
+                if dominated_by[j] == 0 {
+                    next.push(j);
+                }
+            }
+        }
+        fronts.push(std::mem::replace(&mut front, next));
+    }
+    fronts
+}
+
+/// How much room each member of `front` has around it, as the distance
+/// between its neighbours on each objective measured against that
+/// objective's spread on the front, summed. The ends of each objective get
+/// infinite room so the front's extremes are never squeezed out.
+pub fn crowding_distances(objectives: &[Vec<Float>], front: &[usize]) -> Vec<Float> {
+    let mut distances = vec![0.0; front.len()];
+    let objective_count = front.first().map_or(0, |&i| objectives[i].len());
+    let value = |member: usize, k: usize| objectives[front[member]][k];
+    let mut order: Vec<usize> = (0..front.len()).collect();
+    for k in 0..objective_count {
+        order.sort_by(|&a, &b| value(a, k).total_cmp(&value(b, k)));
+        let (Some(&low), Some(&high)) = (order.first(), order.last()) else {
+            continue;
+        };
+        distances[low] = Float::INFINITY;
+        distances[high] = Float::INFINITY;
+        let spread = value(high, k) - value(low, k);
+        if spread <= 0.0 {
+            continue;
+        }
+        for w in order.windows(3) {
+            distances[w[1]] += (value(w[2], k) - value(w[0], k)) / spread;
+        }
+    }
+    distances
+}
+
+/// One score per entry of `objectives` that ranks by Pareto front first and
+/// by crowding distance within a front, for selection that only understands
+/// a single number. With `f` fronts, the first scores from `f` up to
+/// `f + 0.5` and the last from 1 up to 1.5, so no front ever outscores a
+/// better one and the least crowded of a front score highest.
+pub fn pareto_scores(objectives: &[Vec<Float>]) -> Vec<Float> {
+    let fronts = non_dominated_fronts(objectives);
+    let mut scores = vec![0.0; objectives.len()];
+    for (rank, front) in fronts.iter().enumerate() {
+        let base = (fronts.len() - rank) as Float;
+        for (&i, distance) in front.iter().zip(crowding_distances(objectives, front)) {
+            let room = if distance.is_finite() { distance / (1.0 + distance) } else { 1.0 };
+            scores[i] = base + 0.5 * room;
+        }
+    }
+    scores
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two objectives: a front of four, with a tie in the middle, then two
+    /// points each behind the last.
+    fn scores() -> Vec<Vec<Float>> {
+        vec![vec![3.0, 1.0], vec![1.0, 3.0], vec![2.0, 2.0], vec![1.0, 1.0], vec![0.0, 0.0], vec![2.0, 2.0]]
+    }
+
+    #[test]
+    fn fronts_are_peeled_off_best_first() {
+        let mut fronts = non_dominated_fronts(&scores());
+        fronts.iter_mut().for_each(|front| front.sort());
+        assert_eq!(fronts, vec![vec![0, 1, 2, 5], vec![3], vec![4]]);
+        assert!(non_dominated_fronts(&[]).is_empty());
+    }
+
+    #[test]
+    fn crowding_gives_the_ends_infinite_room() {
+        let objectives = vec![vec![0.0, 4.0], vec![1.0, 3.0], vec![3.0, 1.0], vec![4.0, 0.0]];
+        let distances = crowding_distances(&objectives, &[0, 1, 2, 3]);
+        assert_eq!(distances[0], Float::INFINITY);
+        assert_eq!(distances[3], Float::INFINITY);
+        // Neighbours 3 apart on each objective's spread of 4
+        assert!((distances[1] - 1.5).abs() < 1e-6 && (distances[2] - 1.5).abs() < 1e-6, "{distances:?}");
+        assert_eq!(crowding_distances(&[vec![1.0]], &[0]), vec![Float::INFINITY]);
+    }
+
+    #[test]
+    fn scores_rank_fronts_and_then_room() {
+        let scores = pareto_scores(&scores());
+        // Three fronts: the first scores 3 to 3.5, the last 1 to 1.5
+        for (i, (low, high)) in [(0, (3.0, 3.5)), (1, (3.0, 3.5)), (2, (3.0, 3.5)), (3, (2.0, 2.5)), (4, (1.0, 1.5)), (5, (3.0, 3.5))] {
+            assert!((low..=high).contains(&scores[i]), "{i}: {scores:?}");
+        }
+        // The front's extremes have the most room
+        assert_eq!(scores[0], 3.5);
+        assert_eq!(scores[1], 3.5);
+        assert!(scores[2] < 3.5 && scores[5] < 3.5);
+    }
+}
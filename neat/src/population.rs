@@ -0,0 +1,693 @@
+use rand::Rng;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use std::cmp::Ordering;
+
+use crate::Float;
+use crate::genome::{Connectivity, Genome, MutationRates, WeightInit};
+use crate::hall_of_fame::HallOfFame;
+use crate::innovation::InnovationHistory;
+use crate::metrics::{GenerationCallback, GenerationReport, GenerationStats};
+use crate::pareto::pareto_scores;
+use crate::species::Species;
+
+pub struct NeatConfig {
+    pub population_size: usize,
+    pub input_count: usize,
+    pub output_count: usize,
+    pub mutation_rates: MutationRates, // Chance of each mutation an offspring gets, one per offspring
+    pub weight_cap: Float, // Weights are kept within [-weight_cap, weight_cap]
+    pub allow_recurrent: bool,
+    pub bias: bool, // Starting genomes get a bias node feeding every hidden and output node
+    pub hall_of_fame_size: usize, // How many of the best genomes ever seen to keep
+    pub target_fitness: Option<Float>, // Fitness at which the problem counts as solved
+    pub fitness_history_cap: Option<usize>, // Oldest generations are dropped past this many
+    pub compatibility_threshold: Float, // Genomes closer than this to a representative share its species
+    pub excess_coefficient: Float,
+    pub disjoint_coefficient: Float,
+    pub weight_coefficient: Float,
+    pub max_stagnation: usize, // Species that go this many generations without improving are culled
+    pub target_species: Option<ThresholdTarget>, // Adjust the threshold to hold a species count
+    pub crossover_chance: f64, // Offspring bred from two parents rather than cloned from one
+    pub interspecies_mating_rate: f64, // Crossovers whose second parent comes from another species
+    pub complexity_penalty: Float, // Taken off fitness per node and enabled connection before selection; 0 turns it off
+    pub initial_connectivity: Connectivity, // Input-output connections in the starting population
+    pub hidden_seeds: usize, // Hidden nodes the starting population has between every input and output
+    pub weight_init: WeightInit, // How the weights of new connections are drawn, at the start and by mutation
+    pub champion_species_size: Option<usize>, // Species at least this big carry their best genome over unchanged
+    pub disabled_gene_chance: f64, // Crossover keeps a gene disabled in either parent disabled this often
+    pub rank_selection: bool, // Parents picked by fitness rank rather than fitness, so outliers and negative scores can't skew the odds
+    pub seed: Option<u64>, // Same seed, same config and same fitness give the same run; None seeds from the OS
+}
+
+/// Moves the compatibility threshold each generation towards a species count.
+#[derive(Debug, Clone, Copy)]
+pub struct ThresholdTarget {
+    pub species_count: usize,
+    pub step: Float, // How far the threshold moves per generation
+    pub min_threshold: Float,
+    pub max_threshold: Float,
+}
+
+pub struct Neat {
+    pub population: Vec<Genome>,
+    pub config: NeatConfig,
+    pub generation: usize,
+    pub hall_of_fame: HallOfFame,
+    pub species: Vec<Species>,
+    compatibility_threshold: Float,
+    next_species_id: usize,
+    history: InnovationHistory,
+    best_genome: Option<Genome>,
+    best_fitness: Float,
+    fitness_history: Vec<Float>,
+    generation_callback: Option<GenerationCallback>,
+    rng: StdRng,
+}
+
+impl Neat {
+    /// Panics if `config.population_size` is 0, since there'd be nothing to
+    /// evolve.
+    pub fn new(config: NeatConfig) -> Self {
+        assert!(config.population_size >= 1, "population_size must be at least 1");
+        let mut rng = config.seed.map_or_else(StdRng::from_os_rng, StdRng::seed_from_u64);
+        let mut history = InnovationHistory::default();
+        let mut population = Vec::new();
+        for _ in 0..config.population_size {
+            population.push(Self::create_initial_genome(&config, &mut history, &mut rng));
+        }
+        Self {
+            population,
+            hall_of_fame: HallOfFame::new(config.hall_of_fame_size),
+            species: Vec::new(),
+            compatibility_threshold: config.compatibility_threshold,
+            next_species_id: 0,
+            config,
+            generation: 0,
+            history,
+            best_genome: None,
+            best_fitness: 0.0,
+            fitness_history: Vec::new(),
+            generation_callback: None,
+            rng,
+        }
+    }
+
+    /// Has `callback` called at the end of every `evolve`, for logging,
+    /// plotting or anything else that wants to watch the run. Replaces any
+    /// earlier callback.
+    pub fn set_generation_callback(&mut self, callback: GenerationCallback) {
+        self.generation_callback = Some(callback);
+    }
+
+    /// The fittest genome scored by any `evolve` call so far, with its
+    /// `fitness` filled in.
+    pub fn best(&self) -> Option<&Genome> {
+        self.best_genome.as_ref()
+    }
+
+    pub fn best_fitness(&self) -> Float {
+        self.best_fitness
+    }
+
+    /// Best fitness of each scored generation, oldest first. Capped at
+    /// `config.fitness_history_cap` entries.
+    pub fn fitness_history(&self) -> &[Float] {
+        &self.fitness_history
+    }
+
+    /// True when none of the last `window` generations beat the best fitness
+    /// from before them by more than `epsilon`. Needs more than `window`
+    /// generations of history to say yes.
+    pub fn has_stagnated(&self, window: usize, epsilon: Float) -> bool {
+        if self.fitness_history.len() <= window {
+            return false;
+        }
+        let (before, recent) = self.fitness_history.split_at(self.fitness_history.len() - window);
+        let best_before = before.iter().copied().fold(Float::MIN, Float::max);
+        let best_recent = recent.iter().copied().fold(Float::MIN, Float::max);
+        best_recent <= best_before + epsilon
+    }
+
+    /// Threshold speciation currently uses. Starts at
+    /// `config.compatibility_threshold` and only changes with `target_species`.
+    pub fn compatibility_threshold(&self) -> Float {
+        self.compatibility_threshold
+    }
+
+    /// Whether the best fitness so far meets `config.target_fitness`. Always
+    /// false without a target.
+    pub fn reached_target(&self) -> bool {
+        self.config.target_fitness.is_some_and(|target| self.best_fitness >= target)
+    }
+
+    /// `config.input_count` inputs connected to `config.output_count` outputs
+    /// as `config.initial_connectivity` says, through `config.hidden_seeds`
+    /// hidden nodes as well, plus a bias node if `config.bias` is set, with
+    /// weights drawn as `config.weight_init` says.
+    pub fn create_initial_genome(config: &NeatConfig, history: &mut InnovationHistory, rng: &mut impl Rng) -> Genome {
+        let genome = Genome::with_hidden(
+            config.input_count,
+            config.output_count,
+            config.initial_connectivity,
+            config.hidden_seeds,
+            config.weight_init,
+            history,
+            rng,
+        );
+        if config.bias { genome.with_bias(config.weight_init, history, rng) } else { genome }
+    }
+
+    /// Sorts the population into species by compatibility with each species'
+    /// representative, starting new species for genomes that fit none, and
+    /// drops species left without members.
+    fn speciate(&mut self) {
+        for species in &mut self.species {
+            species.members.clear();
+        }
+        for (idx, genome) in self.population.iter().enumerate() {
+            let config = &self.config;
+            let threshold = self.compatibility_threshold;
+            let home = self.species.iter_mut().find(|s| {
+                genome.compatibility_distance(
+                    &s.representative,
+                    config.excess_coefficient,
+                    config.disjoint_coefficient,
+                    config.weight_coefficient,
+                ) < threshold
+            });
+            match home {
+                Some(species) => species.members.push(idx),
+                None => {
+                    let mut species = Species::new(self.next_species_id, genome.clone());
+                    species.members.push(idx);
+                    self.species.push(species);
+                    self.next_species_id += 1;
+                }
+            }
+        }
+        self.species.retain(|s| !s.members.is_empty());
+
+        // Too many species means the threshold is too tight, too few too loose
+        if let Some(target) = self.config.target_species {
+            match self.species.len().cmp(&target.species_count) {
+                Ordering::Greater => self.compatibility_threshold += target.step,
+                Ordering::Less => self.compatibility_threshold -= target.step,
+                Ordering::Equal => {}
+            }
+            self.compatibility_threshold = self.compatibility_threshold.clamp(target.min_threshold, target.max_threshold);
+        }
+    }
+
+    /// Updates each species' best fitness and staleness, then removes those
+    /// stale for longer than `config.max_stagnation`. The species holding the
+    /// generation's best genome is always kept.
+    fn cull_stagnant_species(&mut self, fitnesses: &[Float], best_idx: Option<usize>) {
+        for species in &mut self.species {
+            let best = species.members.iter().map(|&i| fitnesses[i]).fold(Float::MIN, Float::max);
+            if best > species.best_fitness {
+                species.best_fitness = best;
+                species.staleness = 0;
+            } else {
+                species.staleness += 1;
+            }
+        }
+        let max_stagnation = self.config.max_stagnation;
+        self.species.retain(|s| {
+            s.staleness <= max_stagnation || best_idx.is_some_and(|best| s.members.contains(&best))
+        });
+    }
+
+    fn mutate(&mut self, genome: &mut Genome) {
+        genome.mutate_with_rng(
+            &mut self.history,
+            &self.config.mutation_rates,
+            self.config.weight_cap,
+            self.config.allow_recurrent,
+            self.config.weight_init,
+            &mut self.rng,
+        );
+    }
+
+    /// Scores the current population with `fitness` and replaces it with the
+    /// next generation. Returns how the scored generation did. NaN or
+    /// infinite scores count as 0 so one bad evaluation can't poison
+    /// selection, and the stats say how many there were.
+    pub fn evolve<F: Fn(&Genome) -> Float>(&mut self, fitness: F) -> GenerationStats {
+        let mut fitnesses: Vec<Float> = self.population.iter()
+            .map(fitness)
+            .collect();
+        let non_finite = fitnesses.iter().filter(|f| !f.is_finite()).count();
+        for f in fitnesses.iter_mut().filter(|f| !f.is_finite()) {
+            *f = 0.0;
+        }
+        let selection = if self.config.rank_selection { ranks(&fitnesses) } else { fitnesses.clone() };
+        self.advance(&fitnesses, &selection, non_finite)
+    }
+
+    /// `evolve` for problems with competing objectives, each higher is
+    /// better. Parents are picked by Pareto front and crowding distance (see
+    /// `pareto_scores`) rather than by a weighted sum. The first objective
+    /// stands in as the fitness everything else reports, `best` and
+    /// `target_fitness` included. Non-finite objectives count as 0.
+    /// `config.rank_selection` has no say, the fronts being ranks already.
+    pub fn evolve_multi<F: Fn(&Genome) -> Vec<Float>>(&mut self, objectives: F) -> GenerationStats {
+        let mut objectives: Vec<Vec<Float>> = self.population.iter()
+            .map(objectives)
+            .collect();
+        let non_finite = objectives.iter().filter(|o| o.iter().any(|v| !v.is_finite())).count();
+        for v in objectives.iter_mut().flatten().filter(|v| !v.is_finite()) {
+            *v = 0.0;
+        }
+        let primary: Vec<Float> = objectives.iter().map(|o| o.first().copied().unwrap_or(0.0)).collect();
+        self.advance(&primary, &pareto_scores(&objectives), non_finite)
+    }
+
+    /// Records the scored generation under `fitnesses` and breeds the next
+    /// one, picking parents by `selection`. Those only differ under
+    /// `config.rank_selection` and for `evolve_multi`. `non_finite` genomes
+    /// had their scores zeroed on the way in.
+    fn advance(&mut self, fitnesses: &[Float], selection: &[Float], non_finite: usize) -> GenerationStats {
+        self.history.new_generation();
+        for (genome, &f) in self.population.iter_mut().zip(fitnesses) {
+            genome.fitness = f;
+        }
+
+        let total_fitness: Float = fitnesses.iter().sum();
+        let best = fitnesses.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1));
+        let mut best_fitness = 0.0;
+        if let Some((best_idx, &fitness)) = best {
+            let mut best = self.population[best_idx].clone();
+            best.fitness = fitness;
+            self.hall_of_fame.consider(&best);
+            if self.best_genome.is_none() || fitness > self.best_fitness {
+                self.best_fitness = fitness;
+                self.best_genome = Some(best);
+            }
+            best_fitness = fitness;
+        }
+        self.fitness_history.push(best_fitness);
+        if let Some(cap) = self.config.fitness_history_cap
+            && self.fitness_history.len() > cap
+        {
+            self.fitness_history.drain(..self.fitness_history.len() - cap);
+        }
+
+        self.speciate();
+        let species_count = self.species.len();
+        self.cull_stagnant_species(fitnesses, best.map(|(i, _)| i));
+
+        let stats = GenerationStats {
+            generation: self.generation,
+            best_fitness,
+            mean_fitness: total_fitness / fitnesses.len().max(1) as Float,
+            species_count,
+            population_size: self.population.len(),
+            reached_target: self.reached_target(),
+            non_finite,
+        };
+
+        // Members of culled species get no offspring; their share goes to the rest
+        let mut parent_fitnesses = vec![0.0; fitnesses.len()];
+        for &i in self.species.iter().flat_map(|s| &s.members) {
+            let (nodes, connections) = self.population[i].complexity();
+            let size = (nodes + connections) as Float;
+            parent_fitnesses[i] = (selection[i] - self.config.complexity_penalty * size).max(0.0);
+        }
+
+        // Each species breeds in proportion to its summed adjusted fitness,
+        // i.e. its members' fitness shared out over the species' size
+        let adjusted_totals: Vec<Float> = self.species.iter().map(|s| {
+            s.members.iter().map(|&i| parent_fitnesses[i]).sum::<Float>() / s.members.len() as Float
+        }).collect();
+        let allocation = allocate_offspring(&adjusted_totals, self.config.population_size);
+
+        let mut new_population = Vec::with_capacity(self.config.population_size);
+
+        for (species_idx, &count) in allocation.iter().enumerate() {
+            let members = self.species[species_idx].members.clone();
+            let weights: Vec<Float> = members.iter().map(|&i| parent_fitnesses[i]).collect();
+            let mut count = count;
+            // The champion takes one of the species' places, unmutated
+            if count > 0 && self.config.champion_species_size.is_some_and(|min| members.len() >= min) {
+                let champion = *members.iter()
+                    .max_by(|&&a, &&b| selection[a].total_cmp(&selection[b]))
+                    .expect("empty species are dropped");
+                let mut copy = self.population[champion].clone();
+                copy.fitness = 0.0;
+                new_population.push(copy);
+                count -= 1;
+            }
+            for _ in 0..count {
+                let pick = roulette(&weights, &mut self.rng).expect("empty species are dropped");
+                let offspring = self.reproduce(members[pick], &parent_fitnesses);
+                new_population.push(offspring);
+            }
+        }
+        // Only possible with an empty population, which leaves no species
+        while new_population.len() < self.config.population_size {
+            new_population.push(Self::create_initial_genome(&self.config, &mut self.history, &mut self.rng));
+        }
+
+        // Next generation is compared against a member of this one
+        for species in &mut self.species {
+            let representative = *species.members.choose(&mut self.rng).expect("empty species are dropped");
+            species.representative = self.population[representative].clone();
+        }
+
+        let scored = std::mem::replace(&mut self.population, new_population);
+        self.generation += 1;
+        if let Some(callback) = &mut self.generation_callback {
+            callback(&GenerationReport {
+                stats: &stats,
+                best_genome: best.map(|(i, _)| &scored[i]),
+            });
+        }
+        stats
+    }
+
+    fn reproduce(&mut self, parent_idx: usize, parent_fitnesses: &[Float]) -> Genome {
+        let mate = self.pick_mate(parent_idx, parent_fitnesses);
+        let parent = &self.population[parent_idx];
+        let mut offspring = match mate {
+            Some(mate_idx) if self.rng.random_bool(self.config.crossover_chance) => {
+                let mate = &self.population[mate_idx];
+                parent.crossover(mate, self.config.disabled_gene_chance, &mut self.rng)
+            }
+            _ => parent.clone(),
+        };
+        offspring.fitness = 0.0;
+        self.mutate(&mut offspring);
+        offspring
+    }
+
+    /// Roulette-picks a second parent for `parent_idx`: usually from its own
+    /// species, from a different one `config.interspecies_mating_rate` of the
+    /// time. `None` if there's nobody suitable.
+    fn pick_mate(&mut self, parent_idx: usize, parent_fitnesses: &[Float]) -> Option<usize> {
+        let own = self.species.iter().position(|s| s.members.contains(&parent_idx))?;
+        let candidates: Vec<usize> = if self.species.len() > 1 && self.rng.random_bool(self.config.interspecies_mating_rate) {
+            self.species.iter().enumerate()
+                .filter(|(i, _)| *i != own)
+                .flat_map(|(_, s)| s.members.iter().copied())
+                .collect()
+        } else {
+            self.species[own].members.iter().copied().filter(|&i| i != parent_idx).collect()
+        };
+
+        let weights: Vec<Float> = candidates.iter().map(|&i| parent_fitnesses[i]).collect();
+        roulette(&weights, &mut self.rng).map(|pick| candidates[pick])
+    }
+}
+
+/// Splits `total` offspring between species in proportion to `shares` by the
+/// largest-remainder method: everyone gets the whole part of their quota, then
+/// the leftovers go to the largest fractional parts. The counts always add up
+/// to `total` when there's at least one species. All-zero shares split evenly.
+fn allocate_offspring(shares: &[Float], total: usize) -> Vec<usize> {
+    if shares.is_empty() {
+        return Vec::new();
+    }
+    let share_total: Float = shares.iter().sum();
+    let quotas: Vec<Float> = if share_total.is_finite() && share_total > 0.0 {
+        shares.iter().map(|share| share / share_total * total as Float).collect()
+    } else {
+        vec![total as Float / shares.len() as Float; shares.len()]
+    };
+
+    let mut counts: Vec<usize> = quotas.iter().map(|quota| quota.floor() as usize).collect();
+    // Float rounding can push the floors a little over or under; the
+    // remainders make up whatever is left either way
+    let assigned: usize = counts.iter().sum();
+    let mut by_remainder: Vec<usize> = (0..quotas.len()).collect();
+    by_remainder.sort_by(|&a, &b| (quotas[b] - quotas[b].floor()).total_cmp(&(quotas[a] - quotas[a].floor())));
+    if assigned < total {
+        for &i in by_remainder.iter().cycle().take(total - assigned) {
+            counts[i] += 1;
+        }
+    } else {
+        let mut excess = assigned - total;
+        for &i in by_remainder.iter().rev().cycle() {
+            if excess == 0 {
+                break;
+            }
+            if counts[i] > 0 {
+                counts[i] -= 1;
+                excess -= 1;
+            }
+        }
+    }
+    counts
+}
+
+/// Each score's rank, 1 for the lowest up to one per score for the highest.
+/// Equal scores share the lower rank.
+fn ranks(scores: &[Float]) -> Vec<Float> {
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|&a, &b| scores[a].total_cmp(&scores[b]));
+    let mut ranks = vec![0.0; scores.len()];
+    let mut rank = 0.0;
+    for (position, &i) in order.iter().enumerate() {
+        if position == 0 || scores[i] > scores[order[position - 1]] {
+            rank = (position + 1) as Float;
+        }
+        ranks[i] = rank;
+    }
+    ranks
+}
+
+/// Roulette-wheel pick: an index into `weights` with chance proportional to
+/// its weight, or uniformly at random when they're all zero, so a generation
+/// that scored nothing still breeds from its own genomes. `None` only when
+/// `weights` is empty.
+fn roulette(weights: &[Float], rng: &mut impl Rng) -> Option<usize> {
+    if weights.is_empty() {
+        return None;
+    }
+    let total: Float = weights.iter().sum();
+    if !total.is_finite() || total <= 0.0 {
+        return Some(rng.random_range(0..weights.len()));
+    }
+    let pick = rng.random_range(0.0..total);
+    let mut current = 0.0;
+    for (i, &weight) in weights.iter().enumerate() {
+        current += weight;
+        if current > pick {
+            return Some(i);
+        }
+    }
+    // Rounding can leave the running sum just short of the pick
+    weights.iter().rposition(|&weight| weight > 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genome::MutationRates;
+    use crate::problems::{Dataset, FitnessFn, MultiObjectiveFitness, Parsimonious};
+
+    fn config(seed: Option<u64>) -> NeatConfig {
+        NeatConfig {
+            population_size: 30,
+            input_count: 2,
+            output_count: 1,
+            mutation_rates: MutationRates { weight: 0.5, add_connection: 0.3, add_node: 0.2, ..MutationRates::default() },
+            weight_cap: 8.0,
+            allow_recurrent: false,
+            hall_of_fame_size: 5,
+            target_fitness: None,
+            fitness_history_cap: None,
+            compatibility_threshold: 3.0,
+            excess_coefficient: 1.0,
+            disjoint_coefficient: 1.0,
+            weight_coefficient: 0.4,
+            max_stagnation: 15,
+            target_species: None,
+            crossover_chance: 0.75,
+            interspecies_mating_rate: 0.01,
+            complexity_penalty: 0.0,
+            initial_connectivity: Connectivity::Full,
+            hidden_seeds: 0,
+            bias: true,
+            weight_init: WeightInit::default(),
+            champion_species_size: Some(5),
+            disabled_gene_chance: 0.75,
+            rank_selection: false,
+            seed,
+        }
+    }
+
+    /// `config(Some(0))` with offspring that are plain copies of one parent,
+    /// so what gets bred shows who was picked.
+    fn cloning_config(population_size: usize) -> NeatConfig {
+        NeatConfig {
+            population_size,
+            mutation_rates: MutationRates { weight: 0.0, add_connection: 0.0, add_node: 0.0, ..MutationRates::default() },
+            crossover_chance: 0.0,
+            champion_species_size: None,
+            ..config(Some(0))
+        }
+    }
+
+    /// Every genome of the population after `generations` generations of XOR.
+    fn run(seed: Option<u64>, generations: usize) -> Vec<String> {
+        let xor = Dataset::xor();
+        let mut neat = Neat::new(config(seed));
+        for _ in 0..generations {
+            neat.evolve(|genome| xor.fitness(genome));
+        }
+        neat.population.iter().map(Genome::to_text).collect()
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_run() {
+        assert_eq!(run(Some(7), 10), run(Some(7), 10));
+        assert_ne!(run(Some(7), 10), run(Some(8), 10));
+    }
+
+    #[test]
+    fn xor_converges() {
+        // Also worth running with `--features f64`, which nothing else trains under
+        let xor = Dataset { steepness: 4.9, ..Dataset::xor() };
+        let mut neat = Neat::new(NeatConfig {
+            population_size: 150,
+            target_fitness: Some(xor.target_fitness()),
+            ..config(Some(0))
+        });
+        let solved = (0..200).any(|_| neat.evolve(|genome| xor.fitness(genome)).reached_target);
+        assert!(solved, "best fitness {}", neat.best_fitness());
+    }
+
+    /// Each generation's stats, and the champion, from `generations`
+    /// generations of XOR traded off against size.
+    fn run_multi(seed: Option<u64>, generations: usize) -> (Vec<GenerationStats>, Genome) {
+        let problem = Parsimonious(Dataset::xor());
+        let mut neat = Neat::new(config(seed));
+        let stats = (0..generations).map(|_| neat.evolve_multi(|genome| problem.objectives(genome))).collect();
+        (stats, neat.best().expect("the population isn't empty").clone())
+    }
+
+    #[test]
+    fn evolve_multi_runs_parsimonious_xor_end_to_end() {
+        let (stats, champion) = run_multi(Some(3), 10);
+        for (generation, s) in stats.iter().enumerate() {
+            assert_eq!((s.generation, s.population_size), (generation, 30));
+            assert!(s.species_count >= 1);
+            assert!(0.0 < s.mean_fitness && s.mean_fitness <= s.best_fitness && s.best_fitness <= 1.0, "{s:?}");
+        }
+
+        // The champion is scored on its fit, the first objective
+        let best = stats.iter().map(|s| s.best_fitness).fold(0.0, Float::max);
+        assert_eq!(champion.fitness, best);
+        assert_eq!(Dataset::xor().fitness(&champion), best);
+        assert_eq!(champion.validate(), Ok(()));
+
+        let same = run_multi(Some(3), 10);
+        assert_eq!(format!("{:?}", same.0), format!("{stats:?}"));
+        assert_eq!(same.1.to_text(), champion.to_text());
+        assert_ne!(format!("{:?}", run_multi(Some(4), 10).0), format!("{stats:?}"));
+    }
+
+    #[test]
+    fn stagnant_species_are_culled_unless_they_hold_the_best() {
+        let mut neat = Neat::new(NeatConfig { max_stagnation: 2, ..config(Some(0)) });
+        neat.species = (0..4).map(|i| {
+            let mut species = Species::new(i, Genome::default());
+            species.members.push(i);
+            species.best_fitness = if i == 3 { 0.0 } else { 10.0 };
+            species.staleness = 2;
+            species
+        }).collect();
+
+        // 0 and 1 go a third generation without improving, 2 too but it has
+        // the best genome, and 3 improves
+        neat.cull_stagnant_species(&[1.0, 1.0, 5.0, 1.0], Some(2));
+        let kept: Vec<usize> = neat.species.iter().map(|s| s.id).collect();
+        assert_eq!(kept, vec![2, 3]);
+        assert_eq!(neat.species[1].staleness, 0);
+    }
+
+    #[test]
+    fn complexity_penalty_stops_bigger_genomes_breeding() {
+        let mut neat = Neat::new(NeatConfig { complexity_penalty: 0.1, ..cloning_config(20) });
+        let small = neat.population[0].clone();
+        let mut big = small.clone();
+        for _ in 0..3 {
+            big.mutate_add_node(&mut neat.history, &mut neat.rng);
+        }
+        neat.population = [vec![small.clone(); 10], vec![big; 10]].concat();
+
+        // Same score, but 13 nodes and enabled connections at 0.1 each is
+        // worth nothing while 7 still is
+        neat.evolve(|_| 1.0);
+        assert!(neat.population.iter().all(|g| g.complexity() == small.complexity()));
+    }
+
+    #[test]
+    fn offspring_are_allocated_by_largest_remainder() {
+        // Quotas 3.33, 5.0 and 1.67: the spare place goes to the largest remainder
+        assert_eq!(allocate_offspring(&[2.0, 3.0, 1.0], 10), vec![3, 5, 2]);
+        assert_eq!(allocate_offspring(&[0.0, 0.0, 0.0], 7), vec![3, 2, 2]);
+        assert_eq!(allocate_offspring(&[], 7), Vec::<usize>::new());
+        for shares in [vec![1.0; 7], vec![0.1, 0.2, 0.3, 1e-9], vec![1e30, 1.0]] {
+            assert_eq!(allocate_offspring(&shares, 100).iter().sum::<usize>(), 100, "{shares:?}");
+        }
+    }
+
+    #[test]
+    fn big_species_carry_their_champion_over_unchanged() {
+        let weights = |genome: &Genome| -> Vec<Float> { genome.connections.iter().map(|c| c.weight).collect() };
+        let next_generation = |champion_species_size| {
+            let mut neat = Neat::new(NeatConfig {
+                champion_species_size,
+                mutation_rates: MutationRates { weight: 1.0, add_connection: 0.0, add_node: 0.0, ..MutationRates::default() },
+                compatibility_threshold: 100.0,
+                ..cloning_config(20)
+            });
+            let champion = neat.population.iter()
+                .max_by(|a, b| a.connections[0].weight.total_cmp(&b.connections[0].weight))
+                .map(weights)
+                .unwrap();
+            neat.evolve(|genome| genome.connections[0].weight + 10.0);
+            (champion, neat.population.iter().map(weights).collect::<Vec<_>>())
+        };
+
+        let (champion, population) = next_generation(Some(5));
+        assert!(population.contains(&champion));
+        // Without it every offspring gets its weights mutated
+        let (champion, population) = next_generation(None);
+        assert!(!population.contains(&champion));
+    }
+
+    #[test]
+    #[should_panic(expected = "population_size must be at least 1")]
+    fn an_empty_population_is_refused() {
+        Neat::new(NeatConfig { population_size: 0, ..config(Some(0)) });
+    }
+
+    #[test]
+    fn evolving_nothing_starts_afresh() {
+        let mut neat = Neat::new(config(Some(0)));
+        neat.population.clear();
+        let stats = neat.evolve(|_| 1.0);
+        assert_eq!((stats.population_size, stats.species_count, stats.best_fitness), (0, 0, 0.0));
+        assert_eq!(neat.population.len(), neat.config.population_size);
+    }
+
+    #[test]
+    fn non_finite_fitness_counts_as_zero() {
+        let mut neat = Neat::new(config(Some(0)));
+        let mut scores = [Float::NAN, Float::INFINITY, Float::NEG_INFINITY, 2.0].into_iter().cycle();
+        let scores: Vec<Float> = (0..neat.population.len()).map(|_| scores.next().unwrap()).collect();
+        let texts: Vec<String> = neat.population.iter().map(Genome::to_text).collect();
+        let score = |genome: &Genome| scores[texts.iter().position(|t| *t == genome.to_text()).unwrap()];
+
+        let finite_total: Float = scores.iter().filter(|s| s.is_finite()).sum();
+
+        let stats = neat.evolve(score);
+        assert_eq!(stats.non_finite, scores.iter().filter(|s| !s.is_finite()).count());
+        assert_eq!(stats.best_fitness, 2.0);
+        assert!((stats.mean_fitness - finite_total / scores.len() as Float).abs() < 1e-6, "{}", stats.mean_fitness);
+        assert_eq!(neat.population.len(), neat.config.population_size);
+    }
+}
@@ -0,0 +1,187 @@
+use std::{fs, io};
+
+use crate::Float;
+use crate::genome::Genome;
+
+/// A problem genomes can be evolved against: the network shape it needs and
+/// how to score a genome on it. `Neat::evolve` takes any `Fn(&Genome) -> Float`,
+/// so pass `|genome| problem.fitness(genome)`.
+pub trait FitnessFn {
+    fn input_count(&self) -> usize;
+    fn output_count(&self) -> usize;
+    /// Higher is better. Never negative unless `NeatConfig::rank_selection`
+    /// is on, since roulette selection treats negative scores as zero.
+    fn fitness(&self, genome: &Genome) -> Float;
+    /// Fitness at which the problem counts as solved.
+    fn target_fitness(&self) -> Float;
+}
+
+/// How a dataset turns its error, the scaled total squared error, into a
+/// fitness. Parents are picked by roulette, in proportion to fitness, so the
+/// shaping sets the selection pressure: how much better a slightly smaller
+/// error makes a genome's odds. With `NeatConfig::rank_selection` only the
+/// order of the fitnesses counts, as it would under tournament selection, and
+/// every shaping picks parents alike.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FitnessShaping {
+    /// `1 / (1 + error)`, in (0, 1]. Large errors all squash towards 0, so a
+    /// population that's still far off gets little pressure.
+    #[default]
+    Inverse,
+    /// `-error` as is. Negative, so roulette sees nothing but zeros and picks
+    /// at random; only meant for rank selection.
+    NegativeError,
+    /// `exp(-rate * error)`, in (0, 1]. Higher rates favour the best more
+    /// strongly near the solution.
+    Exponential { rate: Float },
+}
+
+impl FitnessShaping {
+    pub fn shape(&self, error: Float) -> Float {
+        match *self {
+            FitnessShaping::Inverse => 1.0 / (1.0 + error),
+            FitnessShaping::NegativeError => -error,
+            FitnessShaping::Exponential { rate } => (-rate * error).exp(),
+        }
+    }
+}
+
+/// A fixed table of input rows and the outputs expected for them, scored on
+/// `error_scale * total squared error` as `shaping` says.
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    pub name: String,
+    pub cases: Vec<(Vec<Float>, Vec<Float>)>,
+    /// Evens out the score between tables of different lengths.
+    pub error_scale: Float,
+    /// Fitness that counts as solved, as `1 / (1 + error)` whatever the
+    /// shaping; `target_fitness()` converts it.
+    pub target_fitness: Float,
+    pub shaping: FitnessShaping,
+    /// Steepness the networks are compiled with, see
+    /// `NeuralNetwork::with_steepness`.
+    pub steepness: Float,
+}
+
+impl Dataset {
+    /// The XOR truth table.
+    pub fn xor() -> Self {
+        Self {
+            name: "xor".to_string(),
+            cases: vec![
+                (vec![0.0, 0.0], vec![0.0]),
+                (vec![0.0, 1.0], vec![1.0]),
+                (vec![1.0, 0.0], vec![1.0]),
+                (vec![1.0, 1.0], vec![0.0]),
+            ],
+            error_scale: 1.0,
+            target_fitness: 0.99,
+            shaping: FitnessShaping::Inverse,
+            steepness: 1.0,
+        }
+    }
+
+    /// Odd parity of `bits` inputs: 1 when an odd number of them are on.
+    /// XOR is parity of 2.
+    pub fn parity(bits: usize) -> Self {
+        let cases: Vec<(Vec<Float>, Vec<Float>)> = (0..1usize << bits).map(|row| {
+            let inputs = (0..bits).map(|bit| ((row >> bit) & 1) as Float).collect();
+            let output = (row.count_ones() % 2) as Float;
+            (inputs, vec![output])
+        }).collect();
+        Self {
+            name: format!("parity{bits}"),
+            error_scale: 4.0 / cases.len() as Float,
+            cases,
+            target_fitness: 0.99,
+            shaping: FitnessShaping::Inverse,
+            steepness: 1.0,
+        }
+    }
+
+    /// Reads a comma-separated table with the expected output in the last
+    /// column and the inputs before it. A first line that doesn't parse as
+    /// numbers is taken as a header and skipped.
+    pub fn from_csv(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut cases = Vec::new();
+        for (i, line) in text.lines().map(str::trim).enumerate().filter(|(_, l)| !l.is_empty()) {
+            let values: Result<Vec<Float>, _> = line.split(',').map(|v| v.trim().parse()).collect();
+            match values {
+                Ok(mut values) if values.len() >= 2 => {
+                    let output = values.pop().expect("checked length");
+                    cases.push((values, vec![output]));
+                }
+                Err(_) if i == 0 => continue,
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("malformed row: {line}"))),
+            }
+        }
+        if cases.is_empty() || cases.iter().any(|(inputs, _)| inputs.len() != cases[0].0.len()) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "rows must all have the same number of columns"));
+        }
+        Ok(Self {
+            name: path.to_string(),
+            error_scale: 4.0 / cases.len() as Float,
+            cases,
+            target_fitness: 0.95,
+            shaping: FitnessShaping::Inverse,
+            steepness: 1.0,
+        })
+    }
+}
+
+impl FitnessFn for Dataset {
+    fn input_count(&self) -> usize {
+        self.cases.first().map_or(0, |(inputs, _)| inputs.len())
+    }
+
+    fn output_count(&self) -> usize {
+        self.cases.first().map_or(0, |(_, outputs)| outputs.len())
+    }
+
+    fn fitness(&self, genome: &Genome) -> Float {
+        let mut network = genome.compile().with_steepness(self.steepness);
+        let mut total_error = 0.0;
+        for (inputs, expected) in &self.cases {
+            network.reset();
+            let outputs = network.activate(inputs);
+            total_error += outputs.iter().zip(expected).map(|(o, e)| (o - e).powi(2)).sum::<Float>();
+        }
+        self.shaping.shape(self.error_scale * total_error)
+    }
+
+    fn target_fitness(&self) -> Float {
+        self.shaping.shape(1.0 / self.target_fitness - 1.0)
+    }
+}
+
+/// A problem scored on several objectives at once, for `Neat::evolve_multi`,
+/// which takes any `Fn(&Genome) -> Vec<Float>`, so pass
+/// `|genome| problem.objectives(genome)`.
+pub trait MultiObjectiveFitness {
+    fn input_count(&self) -> usize;
+    fn output_count(&self) -> usize;
+    /// One score per objective, always in the same order, each higher is
+    /// better. The first is the one reported as fitness.
+    fn objectives(&self, genome: &Genome) -> Vec<Float>;
+}
+
+/// A dataset to fit, traded off against staying small: scored on the
+/// dataset's fitness and on `1 / (1 + nodes + connections)`.
+#[derive(Debug, Clone)]
+pub struct Parsimonious(pub Dataset);
+
+impl MultiObjectiveFitness for Parsimonious {
+    fn input_count(&self) -> usize {
+        self.0.input_count()
+    }
+
+    fn output_count(&self) -> usize {
+        self.0.output_count()
+    }
+
+    fn objectives(&self, genome: &Genome) -> Vec<Float> {
+        let (nodes, connections) = genome.complexity();
+        vec![self.0.fitness(genome), 1.0 / (1.0 + (nodes + connections) as Float)]
+    }
+}
@@ -0,0 +1,29 @@
+use crate::Float;
+use crate::genome::Genome;
+
+/// A group of structurally similar genomes that compete mostly among
+/// themselves. Rebuilt every generation by `Neat::evolve`.
+#[derive(Debug, Clone)]
+pub struct Species {
+    pub id: usize,
+    /// Genomes within the compatibility threshold of this one join the species.
+    pub representative: Genome,
+    /// Indices into `Neat::population` of this generation's members.
+    pub members: Vec<usize>,
+    /// Best member fitness the species has ever had.
+    pub best_fitness: Float,
+    /// Generations since `best_fitness` last improved.
+    pub staleness: usize,
+}
+
+impl Species {
+    pub fn new(id: usize, representative: Genome) -> Self {
+        Self {
+            id,
+            representative,
+            members: Vec::new(),
+            best_fitness: Float::MIN,
+            staleness: 0,
+        }
+    }
+}